@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering::Relaxed},
+    },
+};
+
+use duckdb::{
+    Connection,
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vtab::{
+        BindInfo, InitInfo, TableFunctionInfo, VTab, record_batch_to_duckdb_data_chunk,
+        to_duckdb_logical_type,
+    },
+};
+
+use crate::{
+    registry::Init,
+    sql::{quote_ident, quote_literal},
+    stream_table::vector_size,
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "polars")]
+static NEXT_SCOPED_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Arrow record batches registered as views, by view name.
+///
+/// Shared by all connections of a client or pool, so the batches are kept in
+/// memory once. The views scan the batches with a table function whose name
+/// is unique to this set of views.
+#[derive(Clone)]
+pub(crate) struct ArrowViews {
+    scan_fn: Arc<str>,
+    tables: Arc<Mutex<HashMap<String, Arc<ArrowTable>>>>,
+}
+
+struct ArrowTable {
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+}
+
+impl Default for ArrowViews {
+    fn default() -> Self {
+        let id = NEXT_ID.fetch_add(1, Relaxed);
+        Self {
+            scan_fn: format!("async_duckdb_arrow_scan_{id}").into(),
+            tables: Arc::default(),
+        }
+    }
+}
+
+/// Returns the registry key of the arrow view `name`.
+pub(crate) fn key(name: &str) -> String {
+    format!("arrow:{name}")
+}
+
+impl ArrowViews {
+    fn get(&self, name: &str) -> Option<Arc<ArrowTable>> {
+        let views = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        views.get(name).cloned()
+    }
+
+    /// Stores the batches of view `name` and returns the [`Init`] that
+    /// creates the view on a connection.
+    pub(crate) fn insert(&self, name: &str, schema: SchemaRef, batches: Vec<RecordBatch>) -> Init {
+        let table = Arc::new(ArrowTable { schema, batches });
+        let mut views = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        views.insert(name.to_owned(), table);
+        drop(views);
+
+        let this = self.clone();
+        let sql = format!(
+            "CREATE OR REPLACE TEMP VIEW {} AS SELECT * FROM {}",
+            quote_ident(name),
+            self.scan(name)
+        );
+        Arc::new(move |conn| {
+            this.ensure_registered(conn)?;
+            conn.execute_batch(&sql)
+        })
+    }
+
+    /// Makes `batches` scannable on `conn` for the duration of `func`, which
+    /// is called with the table expression scanning them.
+    #[cfg(feature = "polars")]
+    pub(crate) fn scoped<T>(
+        &self,
+        conn: &Connection,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+        func: impl FnOnce(&str) -> duckdb::Result<T>,
+    ) -> duckdb::Result<T> {
+        let name = format!(
+            "async_duckdb_scoped_{}",
+            NEXT_SCOPED_ID.fetch_add(1, Relaxed)
+        );
+        let table = Arc::new(ArrowTable { schema, batches });
+        let mut views = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        views.insert(name.clone(), table);
+        drop(views);
+
+        let res = self
+            .ensure_registered(conn)
+            .and_then(|()| func(&self.scan(&name)));
+        let mut views = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        views.remove(&name);
+        res
+    }
+
+    /// Returns the table expression scanning the batches stored as `name`.
+    fn scan(&self, name: &str) -> String {
+        format!("{}({})", quote_ident(&self.scan_fn), quote_literal(name))
+    }
+
+    /// Registers the scan function on `conn` unless it already exists.
+    fn ensure_registered(&self, conn: &Connection) -> duckdb::Result<()> {
+        let registered: bool = conn.query_row(
+            "SELECT count(*) > 0 FROM duckdb_functions() WHERE function_name = ?",
+            [&*self.scan_fn],
+            |row| row.get(0),
+        )?;
+        if !registered {
+            conn.register_table_function_with_extra_info::<ArrowViewVTab, _>(&self.scan_fn, self)?;
+        }
+        Ok(())
+    }
+
+    /// Releases the batches of view `name` and returns the [`Init`] that
+    /// drops the view from a connection.
+    pub(crate) fn remove(&self, name: &str) -> Init {
+        let mut views = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        views.remove(name);
+        let sql = format!("DROP VIEW IF EXISTS {}", quote_ident(name));
+        Arc::new(move |conn| conn.execute_batch(&sql))
+    }
+}
+
+struct ArrowViewVTab;
+
+#[derive(Default)]
+struct ScanState {
+    batch: usize,
+    offset: usize,
+}
+
+impl VTab for ArrowViewVTab {
+    type BindData = Arc<ArrowTable>;
+    type InitData = Mutex<ScanState>;
+
+    fn bind(bind: &BindInfo) -> Result<Arc<ArrowTable>, Box<dyn std::error::Error>> {
+        // SAFETY: the extra info is set to `ArrowViews` at registration.
+        let views = unsafe { &*bind.get_extra_info::<ArrowViews>() };
+        let name = bind.get_parameter(0).to_string();
+        let table = views
+            .get(&name)
+            .ok_or_else(|| format!("arrow view {name} is not registered"))?;
+        for field in table.schema.fields() {
+            bind.add_result_column(field.name(), to_duckdb_logical_type(field.data_type())?);
+        }
+        Ok(table)
+    }
+
+    fn init(init: &InitInfo) -> Result<Mutex<ScanState>, Box<dyn std::error::Error>> {
+        init.set_max_threads(1);
+        Ok(Mutex::default())
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let table = func.get_bind_data();
+        let mut state = func
+            .get_init_data()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while let Some(batch) = table.batches.get(state.batch) {
+            if state.offset >= batch.num_rows() {
+                state.batch += 1;
+                state.offset = 0;
+                continue;
+            }
+            if batch.num_columns() != output.num_columns() {
+                return Err("arrow view batch does not match the view schema".into());
+            }
+            let len = vector_size().min(batch.num_rows() - state.offset);
+            record_batch_to_duckdb_data_chunk(&batch.slice(state.offset, len), output)?;
+            state.offset += len;
+            return Ok(());
+        }
+        output.set_len(0);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crossbeam_channel::{RecvTimeoutError, bounded};
+use duckdb::{
+    arrow::{array::ArrayRef, record_batch::RecordBatch},
+    core::DataChunkHandle,
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::{WritableVector, data_chunk_to_arrow, write_arrow_array_to_vector},
+};
+use futures_channel::mpsc::{UnboundedSender, unbounded};
+use futures_util::StreamExt;
+
+use crate::registry::Init;
+
+/// A scalar function implemented by an async function.
+///
+/// The function is invoked with a batch of input rows as an arrow
+/// [`RecordBatch`] and returns one output value per row. It runs on the
+/// caller's runtime, see [`AsyncScalarDriver`].
+pub trait AsyncScalar: Send + Sync + 'static {
+    /// The possible signatures of the function.
+    ///
+    /// See [`VScalar::signatures`].
+    fn signatures() -> Vec<ScalarFunctionSignature>;
+
+    /// Computes the function for a batch of input rows.
+    fn invoke(
+        &self,
+        input: RecordBatch,
+    ) -> impl Future<Output = Result<ArrayRef, Box<dyn std::error::Error + Send + Sync>>> + Send;
+}
+
+/// Options for a scalar function registered with
+/// [`Client::register_async_scalar`](crate::Client::register_async_scalar).
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::AsyncScalarOptions;
+/// # use std::time::Duration;
+/// let options = AsyncScalarOptions::new()
+///     .max_concurrency(4)
+///     .timeout(Duration::from_secs(5));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncScalarOptions {
+    pub(crate) max_concurrency: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl AsyncScalarOptions {
+    /// Returns new [`AsyncScalarOptions`] with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the maximum number of batches processed concurrently.
+    ///
+    /// By default, the concurrency is unlimited.
+    #[must_use]
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
+    /// Specify how long the worker waits for the result of a batch before
+    /// failing the query.
+    ///
+    /// By default, the worker waits indefinitely.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// The driver of a scalar function registered with
+/// [`Client::register_async_scalar`](crate::Client::register_async_scalar).
+///
+/// The driver runs the async function for every batch of input rows sent by
+/// the worker thread. It must be spawned (or otherwise polled) on the
+/// caller's runtime; queries that call the function block until it is, or
+/// until the configured timeout expires. The driver completes once the client
+/// or pool it was registered with has been closed and dropped.
+#[must_use = "async scalar functions produce no values unless the driver is polled"]
+pub struct AsyncScalarDriver {
+    fut: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl Future for AsyncScalarDriver {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+type Reply = crossbeam_channel::Sender<Result<ArrayRef, String>>;
+
+/// A batch of input rows sent from the worker to the driver.
+struct Call {
+    input: RecordBatch,
+    reply: Reply,
+}
+
+/// The state of the registered function, shared with the driver.
+#[derive(Clone)]
+struct Bridge {
+    calls: UnboundedSender<Call>,
+    timeout: Option<Duration>,
+}
+
+/// The [`VScalar`] registered with duckdb on behalf of `S`.
+struct Bridged<S>(PhantomData<S>);
+
+impl<S: AsyncScalar> VScalar for Bridged<S> {
+    type State = Bridge;
+
+    unsafe fn invoke(
+        state: &Bridge,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (tx, rx) = bounded(1);
+        let call = Call {
+            input: data_chunk_to_arrow(input)?,
+            reply: tx,
+        };
+        state
+            .calls
+            .unbounded_send(call)
+            .map_err(|_| "async scalar driver was dropped")?;
+        let res = match state.timeout {
+            Some(timeout) => rx.recv_timeout(timeout).map_err(|err| match err {
+                RecvTimeoutError::Timeout => "async scalar function timed out",
+                RecvTimeoutError::Disconnected => "async scalar driver was dropped",
+            })?,
+            None => rx.recv().map_err(|_| "async scalar driver was dropped")?,
+        };
+        write_arrow_array_to_vector(&res?, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        S::signatures()
+    }
+}
+
+/// Returns the [`Init`] that registers `func` as `name`, and the driver
+/// running it.
+pub(crate) fn async_scalar<S: AsyncScalar>(
+    name: &str,
+    func: S,
+    options: &AsyncScalarOptions,
+) -> (Init, AsyncScalarDriver) {
+    let (calls_tx, calls_rx) = unbounded::<Call>();
+    let func = Arc::new(func);
+    let fut = calls_rx.for_each_concurrent(options.max_concurrency, move |call| {
+        let func = func.clone();
+        async move {
+            let res = func.invoke(call.input).await.map_err(|err| err.to_string());
+            _ = call.reply.send(res);
+        }
+    });
+    let bridge = Bridge {
+        calls: calls_tx,
+        timeout: options.timeout,
+    };
+    let name = name.to_owned();
+    let init: Init = Arc::new(move |conn| {
+        conn.register_scalar_function_with_state::<Bridged<S>>(&name, &bridge)
+    });
+    let driver = AsyncScalarDriver { fut: Box::pin(fut) };
+    (init, driver)
+}
@@ -0,0 +1,120 @@
+use duckdb::Connection;
+
+use crate::{Client, Error};
+
+type Step<T> = Box<dyn FnOnce(&Connection) -> Result<T, duckdb::Error> + Send>;
+
+/// A batch of calls that run back-to-back on a [`Client`], without calls of
+/// other callers in between.
+///
+/// Created with [`Client::batch`]. The calls are sent to the worker thread
+/// as a single command.
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::Client;
+/// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+/// let rows = client
+///     .batch()
+///     .transaction(true)
+///     .execute("INSERT INTO accounts VALUES (1, 100)")
+///     .execute("UPDATE totals SET total = total + 100")
+///     .run()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "a batch does nothing unless run"]
+pub struct Batch<T> {
+    /// `None` if the batch was created by a pool without connections.
+    client: Option<Client>,
+    steps: Vec<Step<T>>,
+    transaction: bool,
+}
+
+impl<T: Send + 'static> Batch<T> {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            client: Some(client),
+            steps: Vec::new(),
+            transaction: false,
+        }
+    }
+
+    /// Returns a batch whose run fails with [`Error::Closed`].
+    pub(crate) fn closed() -> Self {
+        Self {
+            client: None,
+            steps: Vec::new(),
+            transaction: false,
+        }
+    }
+
+    /// Adds a call of `func` to the batch.
+    pub fn conn<F>(mut self, func: F) -> Self
+    where
+        F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+    {
+        self.steps.push(Box::new(func));
+        self
+    }
+
+    /// Specify whether the calls run in a single transaction.
+    ///
+    /// By default, they do not, and the calls before a failing one stay
+    /// applied.
+    pub fn transaction(mut self, transaction: bool) -> Self {
+        self.transaction = transaction;
+        self
+    }
+
+    /// Runs the calls in order, returning their results.
+    ///
+    /// The batch stops at the first failing call and returns its error. In a
+    /// transaction, the changes of all calls are rolled back then.
+    pub async fn run(self) -> Result<Vec<T>, Error> {
+        let (steps, transaction) = (self.steps, self.transaction);
+        self.client
+            .ok_or(Error::Closed)?
+            .conn_mut(move |conn| run(conn, steps, transaction))
+            .await
+    }
+
+    /// Runs the calls in order, blocking the current thread until
+    /// completion.
+    ///
+    /// See [`Batch::run`].
+    pub fn run_blocking(self) -> Result<Vec<T>, Error> {
+        let (steps, transaction) = (self.steps, self.transaction);
+        self.client
+            .ok_or(Error::Closed)?
+            .conn_mut_blocking(move |conn| run(conn, steps, transaction))
+    }
+}
+
+impl Batch<usize> {
+    /// Adds the statement `sql` to the batch, returning the number of rows
+    /// it changed.
+    pub fn execute(self, sql: &str) -> Self {
+        let sql = sql.to_owned();
+        self.conn(move |conn| conn.execute(&sql, []))
+    }
+}
+
+fn run<T>(
+    conn: &mut Connection,
+    steps: Vec<Step<T>>,
+    transaction: bool,
+) -> Result<Vec<T>, duckdb::Error> {
+    if !transaction {
+        return steps.into_iter().map(|step| step(conn)).collect();
+    }
+    let tx = conn.transaction()?;
+    let res = steps
+        .into_iter()
+        .map(|step| step(&tx))
+        .collect::<Result<Vec<T>, _>>()?;
+    tx.commit()?;
+    Ok(res)
+}
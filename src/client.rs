@@ -1,12 +1,65 @@
-use crate::Error;
+use crate::{
+    Error,
+    batch::Batch,
+    close::{ClosePolicy, Worker},
+    copy::{self, DatabaseCopy, ExportDatabaseOptions, ExportOptions, ImportOptions},
+    cursor::{self, Cursor, Cursors},
+    group_commit::{self, Write, WriteOp},
+    health::{Health, HealthCheck, Probe, WorkerHealth},
+    maintenance::{CheckpointInfo, Maintenance, MaintenancePolicy},
+    migrations::{self, Migrations},
+    progress::{self, QueryProgress},
+    registry::Registry,
+    retry::{Retried, RetryPolicy},
+    schema::{
+        self, ColumnInfo, ConstraintInfo, DatabaseInfo, FunctionInfo, IndexInfo, SchemaInfo,
+        TableInfo, ViewInfo,
+    },
+    settings::{self, Setting},
+    statement::{AsyncStatement, Statements},
+    timer::sleep,
+    worker::ThreadBuilder,
+};
 use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
     path::{Path, PathBuf},
+    pin::pin,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::Relaxed},
+    },
     thread,
+    time::{Duration, Instant},
 };
 
-use crossbeam_channel::{Sender, bounded, unbounded};
+#[cfg(feature = "vtab-arrow")]
+use crate::arrow_view;
+#[cfg(feature = "polars")]
+use crate::polars::{self, WriteMode};
+#[cfg(any(feature = "vscalar", feature = "vtab"))]
+use crate::registry::Init;
+#[cfg(feature = "vtab")]
+use crate::stream_table::{self, StreamTableDriver};
+#[cfg(feature = "vscalar")]
+use crate::{
+    async_scalar::{self, AsyncScalar, AsyncScalarDriver, AsyncScalarOptions},
+    registry,
+};
+use crossbeam_channel::{
+    Receiver, RecvTimeoutError, Select, Sender, TryRecvError, bounded, unbounded,
+};
+#[cfg(feature = "vtab")]
+use duckdb::arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
 use duckdb::{Config, Connection};
-use futures_channel::oneshot;
+use futures_channel::{mpsc, oneshot};
+use futures_util::{
+    Stream,
+    future::{self, Either},
+};
+#[cfg(feature = "polars")]
+use polars_core::prelude::DataFrame;
 
 /// A `ClientBuilder` can be used to create a [`Client`] with custom
 /// configuration.
@@ -31,6 +84,16 @@ use futures_channel::oneshot;
 pub struct ClientBuilder {
     pub(crate) path: Option<PathBuf>,
     pub(crate) flagsfn: Option<fn() -> duckdb::Result<Config>>,
+    pub(crate) registry: Arc<Registry>,
+    pub(crate) migrations: Option<Migrations>,
+    pub(crate) maintenance: Option<MaintenancePolicy>,
+    pub(crate) group_commit: Option<Duration>,
+    pub(crate) thread_builder: ThreadBuilder,
+    pub(crate) thread_index: Option<usize>,
+    pub(crate) close_on_drop: Option<Arc<dyn Fn(Error) + Send + Sync>>,
+    pub(crate) health_check: HealthCheck,
+    pub(crate) cursor_ttl: Option<Duration>,
+    pub(crate) retry_policy: RetryPolicy,
 }
 
 impl ClientBuilder {
@@ -58,6 +121,80 @@ impl ClientBuilder {
         self
     }
 
+    /// Specify the [`Migrations`] to apply when the connection is opened.
+    ///
+    /// Opening fails if the migrations cannot be applied, or if applied
+    /// migrations have drifted, see [`Migrations`].
+    #[must_use]
+    pub fn migrations(mut self, migrations: Migrations) -> Self {
+        self.migrations = Some(migrations);
+        self
+    }
+
+    /// Specify the [`MaintenancePolicy`] checkpointing the database in the
+    /// background.
+    ///
+    /// See [`Client::last_checkpoint`].
+    #[must_use]
+    pub fn maintenance(mut self, policy: MaintenancePolicy) -> Self {
+        self.maintenance = Some(policy);
+        self
+    }
+
+    /// Commit the writes submitted with [`Client::write`] within `window`
+    /// of each other in a single transaction.
+    ///
+    /// Without it, every write is committed on its own.
+    #[must_use]
+    pub fn group_commit(mut self, window: Duration) -> Self {
+        self.group_commit = Some(window);
+        self
+    }
+
+    /// Specify the [`ThreadBuilder`] options of the worker thread.
+    #[must_use]
+    pub fn thread_builder(mut self, thread_builder: ThreadBuilder) -> Self {
+        self.thread_builder = thread_builder;
+        self
+    }
+
+    /// Specify the [`HealthCheck`] run by [`Client::health`].
+    #[must_use]
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.health_check = check;
+        self
+    }
+
+    /// Specify how long a [`Cursor`] may stay idle before it expires.
+    ///
+    /// Defaults to five minutes.
+    #[must_use]
+    pub fn cursor_ttl(mut self, ttl: Duration) -> Self {
+        self.cursor_ttl = Some(ttl);
+        self
+    }
+
+    /// Specify the [`RetryPolicy`] returned by [`Client::retry_policy`].
+    ///
+    /// Defaults to [`RetryPolicy::default()`].
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Close the connection once the last handle to the client is dropped,
+    /// passing any error to `on_error`.
+    ///
+    /// The connection is closed on the worker thread, after the queued calls
+    /// have run. Without it, the connection is dropped without reporting
+    /// errors.
+    #[must_use]
+    pub fn close_on_drop<F: Fn(Error) + Send + Sync + 'static>(mut self, on_error: F) -> Self {
+        self.close_on_drop = Some(Arc::new(on_error));
+        self
+    }
+
     /// Returns a new [`Client`] that uses the `ClientBuilder` configuration.
     ///
     /// # Examples
@@ -90,18 +227,175 @@ impl ClientBuilder {
     }
 }
 
+type SessionFunc = Box<dyn FnOnce(&mut Connection, &mut Session) + Send>;
+
 enum Command {
     Func(Box<dyn FnOnce(&mut Connection) + Send>),
+    Write(Box<dyn WriteOp>),
+    Session(SessionFunc),
     Shutdown(Box<dyn FnOnce(Result<(), Error>) + Send>),
 }
 
+/// The state kept on a worker thread between calls.
+pub(crate) struct Session {
+    pub(crate) cursors: Cursors,
+    pub(crate) statements: Statements,
+}
+
+impl Session {
+    fn new(cursor_ttl: Option<Duration>) -> Self {
+        Self {
+            cursors: Cursors::new(cursor_ttl.unwrap_or(cursor::DEFAULT_TTL)),
+            statements: Statements::default(),
+        }
+    }
+}
+
+/// The priority of calls made through a [`Client`].
+///
+/// High priority calls are run before queued normal priority ones. To avoid
+/// starving normal priority calls, one of them is run after every 8 high
+/// priority calls in a row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+}
+
+/// How many high priority commands are run in a row while normal priority
+/// ones are waiting.
+const MAX_HIGH_STREAK: usize = 8;
+
+/// The receiving ends of the command lanes of a worker thread.
+struct Queue {
+    high: Receiver<Command>,
+    normal: Receiver<Command>,
+    high_streak: usize,
+    /// A command taken while collecting writes, run next.
+    stashed: Option<Command>,
+}
+
+impl Queue {
+    fn new(high: Receiver<Command>, normal: Receiver<Command>) -> Self {
+        Self {
+            high,
+            normal,
+            high_streak: 0,
+            stashed: None,
+        }
+    }
+
+    /// Takes the next queued command, preferring high priority ones.
+    fn try_recv(&mut self) -> Result<Command, TryRecvError> {
+        if let Some(cmd) = self.stashed.take() {
+            return Ok(cmd);
+        }
+        if self.high_streak < MAX_HIGH_STREAK
+            && let Ok(cmd) = self.high.try_recv()
+        {
+            self.high_streak += 1;
+            return Ok(cmd);
+        }
+        match self.normal.try_recv() {
+            Ok(cmd) => {
+                self.high_streak = 0;
+                Ok(cmd)
+            }
+            Err(err) => {
+                let cmd = self.high.try_recv().map_err(|_| err)?;
+                self.high_streak += 1;
+                Ok(cmd)
+            }
+        }
+    }
+
+    /// Collects the writes queued within `window` after `first`, stopping at
+    /// the first other command.
+    fn writes(
+        &mut self,
+        first: Box<dyn WriteOp>,
+        window: Option<Duration>,
+    ) -> Vec<Box<dyn WriteOp>> {
+        let mut ops = vec![first];
+        let Some(window) = window else {
+            return ops;
+        };
+        let deadline = Instant::now() + window;
+        loop {
+            match self.try_recv() {
+                Ok(Command::Write(op)) => ops.push(op),
+                Ok(cmd) => {
+                    self.stashed = Some(cmd);
+                    return ops;
+                }
+                Err(TryRecvError::Disconnected) => return ops,
+                Err(TryRecvError::Empty) => {
+                    let mut select = Select::new();
+                    select.recv(&self.high);
+                    select.recv(&self.normal);
+                    if select.ready_deadline(deadline).is_err() {
+                        return ops;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits for the next command, running due maintenance and expiring idle
+    /// cursors in between.
+    fn recv(
+        &mut self,
+        mut maintenance: Option<&mut Maintenance>,
+        session: &mut Session,
+        conn: &Connection,
+    ) -> Option<Command> {
+        loop {
+            // checked before every command, so a busy queue cannot delay it
+            if let Some(maintenance) = maintenance.as_deref_mut() {
+                maintenance.run_if_due(conn);
+            }
+            session.cursors.expire();
+            match self.try_recv() {
+                Ok(cmd) => return Some(cmd),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => {}
+            }
+            let mut select = Select::new();
+            select.recv(&self.high);
+            select.recv(&self.normal);
+            let deadline = [
+                maintenance.as_ref().and_then(|m| m.deadline()),
+                session.cursors.deadline(),
+            ];
+            match deadline.into_iter().flatten().min() {
+                Some(deadline) => _ = select.ready_deadline(deadline),
+                None => _ = select.ready(),
+            }
+        }
+    }
+}
+
 /// Client represents a single duckdb connection that can be used from async
 /// contexts.
 #[derive(Clone)]
 pub struct Client {
     conn_tx: Sender<Command>,
+    high_tx: Sender<Command>,
+    priority: Priority,
+    id: u64,
+    #[cfg_attr(not(any(feature = "vscalar", feature = "vtab")), expect(dead_code))]
+    registry: Arc<Registry>,
+    last_checkpoint: Arc<Mutex<Option<CheckpointInfo>>>,
+    pending: Arc<AtomicUsize>,
+    worker: Arc<Worker>,
+    health: Arc<Health>,
+    retry_policy: Arc<RetryPolicy>,
 }
 
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
 impl Client {
     async fn open_async(builder: ClientBuilder) -> Result<Self, Error> {
         let (open_tx, open_rx) = oneshot::channel();
@@ -123,39 +417,117 @@ impl Client {
     where
         F: FnOnce(Result<Self, Error>) + Send + 'static,
     {
-        thread::spawn(move || {
+        let (thread_builder, thread_index) = (builder.thread_builder.clone(), builder.thread_index);
+        let worker = Arc::new(Worker::default());
+        let worker_ = Arc::clone(&worker);
+        // `func` is handed to the thread once it can be joined, or called
+        // with the error if spawning fails
+        let (spawned_tx, spawned_rx) = bounded::<F>(1);
+        let res = thread_builder.spawn(thread_index, move |res| {
+            let worker = worker_;
+            let Ok(func) = spawned_rx.recv() else {
+                return;
+            };
+            if let Err(err) = res {
+                func(Err(err.into()));
+                return;
+            }
             let (conn_tx, conn_rx) = unbounded();
+            let (high_tx, high_rx) = unbounded();
+            let mut queue = Queue::new(high_rx, conn_rx);
+            let group_commit = builder.group_commit;
+            let close_on_drop = builder.close_on_drop.clone();
+            let health = Arc::new(Health::new(builder.health_check.clone()));
+            let retry_policy = Arc::new(builder.retry_policy.clone());
+            let registry = builder.registry.clone();
+            let last_checkpoint = Arc::default();
+            let pending = Arc::new(AtomicUsize::new(0));
+            let mut maintenance = builder.maintenance.clone().map(|policy| {
+                Maintenance::new(
+                    policy,
+                    builder.path.as_deref(),
+                    Arc::clone(&last_checkpoint),
+                )
+            });
 
-            let mut conn = match Self::create_conn(builder) {
-                Ok(conn) => conn,
+            let (mut conn, mut session) = match Self::create_conn(builder) {
+                Ok(opened) => opened,
                 Err(err) => {
                     func(Err(err));
                     return;
                 }
             };
 
-            let client = Self { conn_tx };
+            worker.set_interrupt(conn.interrupt_handle());
+            let client = Self {
+                conn_tx,
+                high_tx,
+                priority: Priority::Normal,
+                id: NEXT_ID.fetch_add(1, Relaxed),
+                registry,
+                last_checkpoint,
+                pending: Arc::clone(&pending),
+                worker: Arc::clone(&worker),
+                health,
+                retry_policy,
+            };
             func(Ok(client));
 
-            while let Ok(cmd) = conn_rx.recv() {
+            while let Some(cmd) = queue.recv(maintenance.as_mut(), &mut session, &conn) {
                 match cmd {
-                    Command::Func(func) => func(&mut conn),
+                    // dropping a call fails it with `Error::Closed`
+                    cmd @ (Command::Func(_) | Command::Write(_) | Command::Session(_))
+                        if worker.rejecting() =>
+                    {
+                        drop(cmd);
+                        pending.fetch_sub(1, Relaxed);
+                    }
+                    Command::Func(func) => {
+                        func(&mut conn);
+                        pending.fetch_sub(1, Relaxed);
+                    }
+                    Command::Session(func) => {
+                        func(&mut conn, &mut session);
+                        pending.fetch_sub(1, Relaxed);
+                    }
+                    Command::Write(op) => {
+                        let ops = queue.writes(op, group_commit);
+                        let n = ops.len();
+                        group_commit::commit(&conn, ops);
+                        pending.fetch_sub(n, Relaxed);
+                    }
                     Command::Shutdown(func) => match conn.close() {
                         Ok(()) => {
+                            drop(session);
                             func(Ok(()));
                             return;
                         }
                         Err((c, e)) => {
                             conn = c;
+                            worker.reject(false);
                             func(Err(e.into()));
                         }
                     },
                 }
             }
+            // every handle to the client was dropped
+            if let Some(on_error) = close_on_drop
+                && let Err((_, err)) = conn.close()
+            {
+                on_error(err.into());
+            }
         });
+        match res {
+            Ok(thread) => {
+                worker.set_thread(thread);
+                _ = spawned_tx.send(func);
+            }
+            Err(err) => func(Err(err.into())),
+        }
     }
 
-    fn create_conn(mut builder: ClientBuilder) -> Result<Connection, Error> {
+    /// Opens the connection of the worker and its session.
+    fn create_conn(mut builder: ClientBuilder) -> Result<(Connection, Session), Error> {
         let path = builder.path.take().unwrap_or_else(|| ":memory:".into());
         let config = if let Some(flagsfn) = builder.flagsfn {
             flagsfn()?
@@ -163,7 +535,167 @@ impl Client {
             Config::default()
         };
         let conn = Connection::open_with_flags(path, config)?;
-        Ok(conn)
+        if let Some(policy) = &builder.maintenance {
+            Maintenance::configure(policy, &conn)?;
+        }
+        if let Some(migrations) = &builder.migrations {
+            migrations::up(&conn, migrations)?;
+        }
+        builder.registry.apply(&conn)?;
+        Ok((conn, Session::new(builder.cursor_ttl)))
+    }
+
+    /// Returns the id of this client, unique within the process.
+    ///
+    /// The id is attached to the [`ErrorContext`](crate::ErrorContext) of
+    /// errors produced by this client.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the last checkpoint run by the
+    /// [`MaintenancePolicy`](ClientBuilder::maintenance), if any.
+    #[must_use]
+    pub fn last_checkpoint(&self) -> Option<CheckpointInfo> {
+        *self
+            .last_checkpoint
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of calls queued or running on this client.
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.load(Relaxed)
+    }
+
+    /// Returns whether the last health probe succeeded, or none ran yet.
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.health.is_healthy()
+    }
+
+    /// Probes the worker with the [`HealthCheck`] of the client.
+    ///
+    /// The probe is queued like any other call of this handle's priority, so
+    /// its latency includes the time spent waiting behind queued calls. A
+    /// worker whose probe fails is skipped by the routing of a
+    /// [`Pool`](crate::Pool) until a probe succeeds again. A probe that
+    /// timed out is not run once its turn comes.
+    pub async fn health(&self) -> WorkerHealth {
+        let queue_depth = self.pending();
+        let start = Instant::now();
+        let stale = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = oneshot::channel();
+        let res = match self.probe(&stale, move |res| _ = tx.send(res)) {
+            Ok(()) => {
+                let timeout = pin!(sleep(self.health.check.timeout));
+                match future::select(rx, timeout).await {
+                    Either::Left((res, _)) => Some(res.unwrap_or(Err(Error::Closed))),
+                    Either::Right((timer, _)) => {
+                        stale.store(true, Relaxed);
+                        timer.err().map(Err)
+                    }
+                }
+            }
+            Err(err) => Some(Err(err)),
+        };
+        self.health.record(self.id, queue_depth, start, res)
+    }
+
+    /// Probes the worker with the [`HealthCheck`] of the client, blocking the
+    /// current thread.
+    ///
+    /// See [`Client::health`].
+    #[must_use]
+    pub fn health_blocking(&self) -> WorkerHealth {
+        let probe = self.send_probe();
+        self.wait_probe(probe, self.health.deadline())
+    }
+
+    /// Sends the health probe without waiting for it.
+    pub(crate) fn send_probe(&self) -> Probe {
+        let queue_depth = self.pending();
+        let stale = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = bounded(1);
+        let rx = self.probe(&stale, move |res| _ = tx.send(res)).map(|()| rx);
+        Probe {
+            queue_depth,
+            start: Instant::now(),
+            rx,
+            stale,
+        }
+    }
+
+    /// Waits until `deadline` for the result of `probe`.
+    pub(crate) fn wait_probe(&self, probe: Probe, deadline: Instant) -> WorkerHealth {
+        let res = match probe.rx {
+            Ok(rx) => match rx.recv_deadline(deadline) {
+                Ok(res) => Some(res),
+                Err(RecvTimeoutError::Timeout) => {
+                    probe.stale.store(true, Relaxed);
+                    None
+                }
+                Err(RecvTimeoutError::Disconnected) => Some(Err(Error::Closed)),
+            },
+            Err(err) => Some(Err(err)),
+        };
+        self.health
+            .record(self.id, probe.queue_depth, probe.start, res)
+    }
+
+    /// Queues the health probe, passing its result to `reply` unless it
+    /// became `stale` while queued.
+    fn probe<F>(&self, stale: &Arc<AtomicBool>, reply: F) -> Result<(), Error>
+    where
+        F: FnOnce(Result<(), Error>) + Send + 'static,
+    {
+        let (id, sql, stale) = (self.id, self.health.check.probe.clone(), Arc::clone(stale));
+        self.send(Command::Func(Box::new(move |conn| {
+            // nobody waits for a probe that timed out
+            if !stale.load(Relaxed) {
+                reply(timed(id, &sql, || conn.execute_batch(&sql)));
+            }
+        })))
+    }
+
+    /// Returns a handle to this client whose calls have the given
+    /// `priority`.
+    #[must_use]
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a handle to this client whose calls run before the queued
+    /// calls of normal priority.
+    ///
+    /// See [`Priority`].
+    #[must_use]
+    pub fn high_priority(&self) -> Self {
+        self.with_priority(Priority::High)
+    }
+
+    /// Returns the priority of the calls made through this handle.
+    #[must_use]
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    fn send(&self, cmd: Command) -> Result<(), Error> {
+        if self.worker.rejecting() {
+            return Err(Error::Closed);
+        }
+        let tx = match self.priority {
+            Priority::Normal => &self.conn_tx,
+            Priority::High => &self.high_tx,
+        };
+        self.pending.fetch_add(1, Relaxed);
+        tx.send(cmd).map_err(|err| {
+            self.pending.fetch_sub(1, Relaxed);
+            err.into()
+        })
     }
 
     /// Invokes the provided function with a [`duckdb::Connection`].
@@ -173,10 +705,10 @@ impl Client {
         T: Send + 'static,
     {
         let (tx, rx) = oneshot::channel();
-        self.conn_tx.send(Command::Func(Box::new(move |conn| {
-            _ = tx.send(func(conn));
+        self.send(Command::Func(Box::new(move |conn| {
+            _ = tx.send(func(conn).map_err(Error::from));
         })))?;
-        Ok(rx.await??)
+        rx.await?
     }
 
     /// Invokes the provided function with a mutable [`duckdb::Connection`].
@@ -186,25 +718,542 @@ impl Client {
         T: Send + 'static,
     {
         let (tx, rx) = oneshot::channel();
-        self.conn_tx.send(Command::Func(Box::new(move |conn| {
-            _ = tx.send(func(conn));
+        self.send(Command::Func(Box::new(move |conn| {
+            _ = tx.send(func(conn).map_err(Error::from));
         })))?;
-        Ok(rx.await??)
+        rx.await?
+    }
+
+    /// Runs the query `sql` with `params` and returns a [`Cursor`] to fetch
+    /// its result in pages.
+    ///
+    /// The query runs on the connection of this client and its result is
+    /// kept on the worker thread until the cursor is closed, dropped or
+    /// expires.
+    pub async fn open_cursor<P>(&self, sql: &str, params: P) -> Result<Cursor, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        let (sql, client_id) = (sql.to_owned(), self.id);
+        let (id, schema) = self
+            .session(move |conn, session| {
+                timed(client_id, &sql, || session.cursors.open(conn, &sql, params))
+            })
+            .await?;
+        Ok(Cursor::new(self.clone(), id, schema))
+    }
+
+    /// Prepares `sql` on the worker thread of this client, returning an
+    /// [`AsyncStatement`] that can be run many times with different
+    /// parameters.
+    pub async fn prepare(&self, sql: &str) -> Result<AsyncStatement, Error> {
+        let (sql, client_id) = (sql.to_owned(), self.id);
+        let sql_ = sql.clone();
+        let prepared = self
+            .session(move |conn, session| {
+                timed(client_id, &sql_, || session.statements.prepare(conn, &sql_))
+            })
+            .await?;
+        Ok(AsyncStatement::new(self.clone(), sql, prepared))
+    }
+
+    /// Invokes the provided function with the connection and the state kept
+    /// on the worker thread.
+    pub(crate) async fn session<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Connection, &mut Session) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Session(Box::new(move |conn, session| {
+            _ = tx.send(func(conn, session));
+        })))?;
+        rx.await?
+    }
+
+    /// Sends `func` to the worker thread without waiting for it to run.
+    pub(crate) fn session_detached<F>(&self, func: F)
+    where
+        F: FnOnce(&mut Connection, &mut Session) + Send + 'static,
+    {
+        _ = self.send(Command::Session(Box::new(func)));
+    }
+
+    /// Returns a new [`Batch`] of calls that run back-to-back on this
+    /// client, optionally in a single transaction.
+    pub fn batch<T: Send + 'static>(&self) -> Batch<T> {
+        Batch::new(self.clone())
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] inside a
+    /// transaction, sharing the commit with other writes if
+    /// [`ClientBuilder::group_commit`] is set.
+    ///
+    /// Only the caller whose function fails sees its error: the transaction
+    /// is rolled back and the other writes are run again without it, so the
+    /// function may be invoked multiple times. It must not begin or end
+    /// transactions itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use async_duckdb::Client;
+    /// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+    /// client
+    ///     .write(|conn| conn.execute("INSERT INTO events VALUES (?)", [1]))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn write<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: Fn(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Write(Box::new(Write::new(func, |res| {
+            _ = tx.send(res);
+        }))))?;
+        rx.await?
+    }
+
+    /// Invokes the provided function with a mutable [`duckdb::Connection`],
+    /// retrying transient errors according to `policy`.
+    ///
+    /// The function may be invoked multiple times and so must be idempotent.
+    /// If it fails with an open transaction, the transaction is rolled back
+    /// before the next attempt. A query interrupted because the client was
+    /// closing is retried too, which then fails with [`Error::Closed`].
+    pub async fn conn_mut_retry<F, T>(
+        &self,
+        policy: &RetryPolicy,
+        func: F,
+    ) -> Result<Retried<T>, Error>
+    where
+        F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        retry(policy, func, self.clone(), Self::clone).await
+    }
+
+    /// Returns the [`RetryPolicy`] set with [`ClientBuilder::retry_policy`].
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Returns `true` if `err` should be retried under `policy`: a
+    /// transient error, or a query interrupted because this client was
+    /// closing.
+    fn should_retry(&self, policy: &RetryPolicy, err: &Error) -> bool {
+        policy.should_retry(err) || (err.is_interrupted() && self.worker.interrupted())
+    }
+
+    /// Applies the pending `migrations`, returning their versions.
+    ///
+    /// See [`Migrations`].
+    pub async fn migrate(&self, migrations: &Migrations) -> Result<Vec<u64>, Error> {
+        let migrations = migrations.clone();
+        self.conn(move |conn| Ok(migrations::up(conn, &migrations)))
+            .await?
+    }
+
+    /// Reverts the applied `migrations` newer than version `target`, newest
+    /// first, returning their versions.
+    ///
+    /// All reverted migrations must have [down](crate::Migration::down) SQL.
+    pub async fn migrate_down(
+        &self,
+        migrations: &Migrations,
+        target: u64,
+    ) -> Result<Vec<u64>, Error> {
+        let migrations = migrations.clone();
+        self.conn(move |conn| Ok(migrations::down(conn, &migrations, target)))
+            .await?
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] and
+    /// reports the progress of the queries it runs.
+    ///
+    /// Returns the future of the result, which must be polled for the
+    /// function to run, and a stream of [`QueryProgress`] updates that ends
+    /// once the function has returned. The progress is polled from a side
+    /// thread while the worker executes; queries too short to be measured
+    /// produce no updates.
+    pub fn conn_with_progress<F, T>(
+        &self,
+        func: F,
+    ) -> (
+        impl Future<Output = Result<T, Error>> + Send + use<F, T>,
+        impl Stream<Item = QueryProgress> + Send + Unpin + use<F, T>,
+    )
+    where
+        F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        conn_with_progress(Ok(self.clone()), func)
     }
 
-    /// Closes the underlying duckdb connection.
+    /// Applies a duckdb setting with `SET` and verifies the change by reading
+    /// it back from `duckdb_settings()`.
+    ///
+    /// The name may be an alias, e.g. `worker_threads` for `threads`. Returns
+    /// an [`Error::PragmaUpdate`] if the value read back differs (ignoring
+    /// case) from `value`. Note that duckdb normalizes some values, e.g.
+    /// memory sizes, so these must be given in their normalized form.
+    pub async fn set_setting<V: Display>(&self, name: &str, value: V) -> Result<(), Error> {
+        let (name, exp) = (name.to_owned(), value.to_string());
+        let (name_, exp_) = (name.clone(), exp.clone());
+        let got = self
+            .conn(move |conn| settings::set(conn, &name_, &exp_))
+            .await
+            .map_err(|err| err.with_sql(settings::set_sql(&name, &exp)))?;
+        settings::verify(&name, &exp, got)
+    }
+
+    /// Returns all duckdb settings, keyed by name.
+    pub async fn settings(&self) -> Result<HashMap<String, Setting>, Error> {
+        self.conn(settings::all).await
+    }
+
+    /// Returns the attached databases.
+    pub async fn databases(&self) -> Result<Vec<DatabaseInfo>, Error> {
+        self.conn(schema::databases).await
+    }
+
+    /// Returns all schemas.
+    pub async fn schemas(&self) -> Result<Vec<SchemaInfo>, Error> {
+        self.conn(schema::schemas).await
+    }
+
+    /// Returns all tables.
+    pub async fn tables(&self) -> Result<Vec<TableInfo>, Error> {
+        self.conn(schema::tables).await
+    }
+
+    /// Returns all views, excluding built-in ones.
+    pub async fn views(&self) -> Result<Vec<ViewInfo>, Error> {
+        self.conn(schema::views).await
+    }
+
+    /// Returns the columns of all tables and views named `table`.
+    pub async fn columns(&self, table: &str) -> Result<Vec<ColumnInfo>, Error> {
+        let table = table.to_owned();
+        self.conn(move |conn| schema::columns(conn, &table)).await
+    }
+
+    /// Returns all indexes.
+    pub async fn indexes(&self) -> Result<Vec<IndexInfo>, Error> {
+        self.conn(schema::indexes).await
+    }
+
+    /// Returns the constraints of all tables named `table`.
+    pub async fn constraints(&self, table: &str) -> Result<Vec<ConstraintInfo>, Error> {
+        let table = table.to_owned();
+        self.conn(move |conn| schema::constraints(conn, &table))
+            .await
+    }
+
+    /// Returns all functions and macros, including built-in ones.
+    pub async fn functions(&self) -> Result<Vec<FunctionInfo>, Error> {
+        self.conn(schema::functions).await
+    }
+
+    /// Copies the result of `query` to the file at `path`, returning the
+    /// number of rows written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use async_duckdb::{Client, ParquetOptions};
+    /// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+    /// let rows = client
+    ///     .export("SELECT * FROM events", "events.parquet", ParquetOptions::new())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn export<P: AsRef<Path>>(
+        &self,
+        query: &str,
+        path: P,
+        options: impl Into<ExportOptions>,
+    ) -> Result<usize, Error> {
+        let query = query.to_owned();
+        let path = path.as_ref().to_string_lossy().into_owned();
+        let options = options.into();
+        self.conn(move |conn| copy::export(conn, &query, &path, &options))
+            .await
+    }
+
+    /// Copies the file at `path` into the existing `table`, returning the
+    /// number of rows read.
+    ///
+    /// `table` may be schema qualified, e.g. `staging.events`.
+    pub async fn import<P: AsRef<Path>>(
+        &self,
+        table: &str,
+        path: P,
+        options: impl Into<ImportOptions>,
+    ) -> Result<usize, Error> {
+        let table = table.to_owned();
+        let path = path.as_ref().to_string_lossy().into_owned();
+        let options = options.into();
+        self.conn(move |conn| copy::import(conn, &table, &path, &options))
+            .await
+    }
+
+    /// Copies the whole database, e.g. an in-memory one, into a new database
+    /// file at `path`.
+    ///
+    /// The file is attached and filled with `COPY FROM DATABASE`.
+    pub async fn persist_to<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        let path = path.as_ref().to_owned();
+        self.conn(move |conn| Ok(copy::persist_to(conn, &path)))
+            .await?
+    }
+
+    /// Copies the whole database file at `path` into this database, e.g. to
+    /// work on it in memory.
+    pub async fn load_from<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        let path = path.as_ref().to_owned();
+        self.conn(move |conn| Ok(copy::load_from(conn, &path)))
+            .await?
+    }
+
+    /// Exports the schema and data of the database into the directory `dir`
+    /// with `EXPORT DATABASE`, writing the tables in the format of `options`.
+    ///
+    /// See [`Client::import_database`].
+    pub async fn export_database<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: impl Into<ExportDatabaseOptions>,
+    ) -> Result<DatabaseCopy, Error> {
+        let dir = dir.as_ref().to_owned();
+        let options = options.into();
+        self.conn(move |conn| Ok(copy::export_database(conn, &dir, &options)))
+            .await?
+    }
+
+    /// Imports a database exported with [`Client::export_database`] from the
+    /// directory `dir`.
+    pub async fn import_database<P: AsRef<Path>>(&self, dir: P) -> Result<DatabaseCopy, Error> {
+        let dir = dir.as_ref().to_owned();
+        self.conn(move |conn| Ok(copy::import_database(conn, &dir)))
+            .await?
+    }
+
+    /// Runs the query `sql` with `params` and collects the result into a
+    /// polars [`DataFrame`].
+    ///
+    /// The result is transferred as arrow data, without per-row conversion.
+    #[cfg(feature = "polars")]
+    pub async fn query_polars<P>(&self, sql: &str, params: P) -> Result<DataFrame, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        let sql = sql.to_owned();
+        self.conn(move |conn| polars::query(conn, &sql, params))
+            .await
+    }
+
+    /// Writes the polars [`DataFrame`] `df` into `table` according to `mode`,
+    /// returning the number of rows written.
+    ///
+    /// The columns are scanned as arrow data, without per-row conversion.
+    #[cfg(feature = "polars")]
+    pub async fn write_polars(
+        &self,
+        table: &str,
+        df: &DataFrame,
+        mode: WriteMode,
+    ) -> Result<usize, Error> {
+        let table = table.to_owned();
+        let df = df.clone();
+        let views = self.registry.arrow_views.clone();
+        self.conn(move |conn| polars::write(conn, &views, &table, &df, mode))
+            .await
+    }
+
+    /// Registers the scalar function `S` as `name`.
+    ///
+    /// The function is also registered on any connection opened later on
+    /// behalf of this client. Registering a function under a name that is
+    /// already registered fails with [`Error::AlreadyRegistered`].
+    #[cfg(feature = "vscalar")]
+    pub async fn register_scalar<S>(&self, name: &str) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Default,
+    {
+        self.register(name, registry::scalar::<S>(name), false)
+            .await
+    }
+
+    /// Registers the scalar function `S` with a custom `state` as `name`.
+    ///
+    /// See [`Client::register_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub async fn register_scalar_with_state<S>(
+        &self,
+        name: &str,
+        state: S::State,
+    ) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Clone,
+    {
+        self.register(name, registry::scalar_with_state::<S>(name, state), false)
+            .await
+    }
+
+    /// Registers the async scalar function `func` as `name`.
+    ///
+    /// Each batch of input rows is sent to the returned [`AsyncScalarDriver`],
+    /// which must be spawned on the caller's runtime, while the worker thread
+    /// waits for the result.
+    #[cfg(feature = "vscalar")]
+    pub async fn register_async_scalar<S: AsyncScalar>(
+        &self,
+        name: &str,
+        func: S,
+        options: AsyncScalarOptions,
+    ) -> Result<AsyncScalarDriver, Error> {
+        let (init, driver) = async_scalar::async_scalar(name, func, &options);
+        self.register(name, init, false).await?;
+        Ok(driver)
+    }
+
+    /// Registers a table function `name` whose rows are produced by streams
+    /// of arrow record batches with the given `schema`.
+    ///
+    /// Every scan of the table, e.g. `SELECT * FROM name()`, calls `factory`
+    /// to create a new stream. The streams are polled by the returned
+    /// [`StreamTableDriver`], which must be spawned on the caller's runtime.
+    /// Queries scanning the table must not block the thread that polls the
+    /// driver, see [`StreamTableDriver`].
+    #[cfg(feature = "vtab")]
+    pub async fn register_stream_table<F, S>(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        factory: F,
+    ) -> Result<StreamTableDriver, Error>
+    where
+        F: Fn() -> S + Send + 'static,
+        S: Stream<Item = RecordBatch> + Send + 'static,
+    {
+        let (init, driver) = stream_table::stream_table(name, schema, factory);
+        self.register(name, init, false).await?;
+        Ok(driver)
+    }
+
+    /// Registers in-memory arrow record `batches` as the temporary view
+    /// `name`, replacing any previous view of the same name.
+    ///
+    /// The batches are handed to the worker thread once and scanned in
+    /// place by every query of the view.
+    #[cfg(feature = "vtab-arrow")]
+    pub async fn register_arrow(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let init = self.registry.arrow_views.insert(name, schema, batches);
+        self.register(&arrow_view::key(name), init, true).await
+    }
+
+    /// Drops the view `name` registered with [`Client::register_arrow`] and
+    /// releases its batches.
+    #[cfg(feature = "vtab-arrow")]
+    pub async fn unregister_arrow(&self, name: &str) -> Result<(), Error> {
+        let init = self.registry.arrow_views.remove(name);
+        self.registry.remove(&arrow_view::key(name));
+        self.apply(name, init).await
+    }
+
+    /// Records `init` in the registry and applies it to the connection,
+    /// rolling the registry back if that fails.
+    ///
+    /// Recording first ensures connections opened meanwhile apply it too.
+    /// Unless `replace` is set, a registered `name` is rejected.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) async fn register(
+        &self,
+        name: &str,
+        init: Init,
+        replace: bool,
+    ) -> Result<(), Error> {
+        let replaced = self.registry.insert(name, init.clone(), replace)?;
+        self.apply(name, init)
+            .await
+            .inspect_err(|_| self.registry.restore(name, replaced))
+    }
+
+    /// Applies `init` to the connection without recording it.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) async fn apply(&self, name: &str, init: Init) -> Result<(), Error> {
+        self.conn(move |conn| init(conn))
+            .await
+            .map_err(|err| Error::Registration {
+                name: name.to_owned(),
+                source: Box::new(err),
+            })
+    }
+
+    /// Closes the underlying duckdb connection, after the queued calls have
+    /// run, and waits for the worker thread to exit.
     ///
     /// After this method returns, all calls to `self::conn()` or
     /// `self::conn_mut()` will return an [`Error::Closed`] error.
     pub async fn close(&self) -> Result<(), Error> {
+        self.close_with(ClosePolicy::default()).await
+    }
+
+    /// Closes the underlying duckdb connection according to `policy`, and
+    /// waits for the worker thread to exit.
+    ///
+    /// If closing fails, the client stays usable.
+    pub async fn close_with(&self, policy: ClosePolicy) -> Result<(), Error> {
+        self.close_until(policy.reject_queued, policy.deadline())
+            .await
+    }
+
+    /// Closes the underlying duckdb connection, interrupting the running
+    /// query at `deadline`.
+    pub(crate) async fn close_until(
+        &self,
+        reject_queued: bool,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if reject_queued {
+            self.worker.reject(true);
+        }
         let (tx, rx) = oneshot::channel();
         let func = Box::new(|res| _ = tx.send(res));
         if self.conn_tx.send(Command::Shutdown(func)).is_err() {
             // If the worker thread has already shut down, return Ok here.
-            return Ok(());
+            return self.worker.join_async().await;
         }
+        let res = match deadline {
+            Some(deadline) => {
+                let grace_period = deadline.saturating_duration_since(Instant::now());
+                match future::select(rx, pin!(sleep(grace_period))).await {
+                    Either::Left((res, _)) => res,
+                    // a timer that failed to start ends the grace period
+                    Either::Right((_, rx)) => {
+                        self.worker.interrupt();
+                        rx.await
+                    }
+                }
+            }
+            None => rx.await,
+        };
         // If receiving fails, the connection is already closed.
-        rx.await.unwrap_or(Ok(()))
+        res.unwrap_or(Ok(()))?;
+        self.worker.join_async().await
     }
 
     /// Invokes the provided function with a [`duckdb::Connection`], blocking
@@ -215,10 +1264,51 @@ impl Client {
         T: Send + 'static,
     {
         let (tx, rx) = bounded(1);
-        self.conn_tx.send(Command::Func(Box::new(move |conn| {
-            _ = tx.send(func(conn));
+        self.send(Command::Func(Box::new(move |conn| {
+            _ = tx.send(func(conn).map_err(Error::from));
+        })))?;
+        rx.recv()?
+    }
+
+    /// Runs the query `sql` with `params` and returns a [`Cursor`] to fetch
+    /// its result in pages, blocking the current thread.
+    ///
+    /// See [`Client::open_cursor`].
+    pub fn open_cursor_blocking<P>(&self, sql: &str, params: P) -> Result<Cursor, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        let (sql, client_id) = (sql.to_owned(), self.id);
+        let (id, schema) = self.session_blocking(move |conn, session| {
+            timed(client_id, &sql, || session.cursors.open(conn, &sql, params))
+        })?;
+        Ok(Cursor::new(self.clone(), id, schema))
+    }
+
+    /// Prepares `sql` on the worker thread of this client, blocking the
+    /// current thread.
+    ///
+    /// See [`Client::prepare`].
+    pub fn prepare_blocking(&self, sql: &str) -> Result<AsyncStatement, Error> {
+        let (sql, client_id) = (sql.to_owned(), self.id);
+        let sql_ = sql.clone();
+        let prepared = self.session_blocking(move |conn, session| {
+            timed(client_id, &sql_, || session.statements.prepare(conn, &sql_))
+        })?;
+        Ok(AsyncStatement::new(self.clone(), sql, prepared))
+    }
+
+    /// Blocking version of [`Client::session`].
+    pub(crate) fn session_blocking<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Connection, &mut Session) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = bounded(1);
+        self.send(Command::Session(Box::new(move |conn, session| {
+            _ = tx.send(func(conn, session));
         })))?;
-        Ok(rx.recv()??)
+        rx.recv()?
     }
 
     /// Invokes the provided function with a mutable [`duckdb::Connection`],
@@ -229,24 +1319,513 @@ impl Client {
         T: Send + 'static,
     {
         let (tx, rx) = bounded(1);
-        self.conn_tx.send(Command::Func(Box::new(move |conn| {
-            _ = tx.send(func(conn));
+        self.send(Command::Func(Box::new(move |conn| {
+            _ = tx.send(func(conn).map_err(Error::from));
         })))?;
-        Ok(rx.recv()??)
+        rx.recv()?
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] inside a
+    /// transaction, blocking the current thread until completion.
+    ///
+    /// See [`Client::write`].
+    pub fn write_blocking<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: Fn(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = bounded(1);
+        self.send(Command::Write(Box::new(Write::new(func, move |res| {
+            _ = tx.send(res);
+        }))))?;
+        rx.recv()?
     }
 
-    /// Closes the underlying duckdb connection, blocking the current thread
-    /// until complete.
+    /// Invokes the provided function with a mutable [`duckdb::Connection`],
+    /// retrying transient errors according to `policy` and blocking the
+    /// current thread until completion.
+    ///
+    /// See [`Client::conn_mut_retry`].
+    pub fn conn_mut_retry_blocking<F, T>(
+        &self,
+        policy: &RetryPolicy,
+        func: F,
+    ) -> Result<Retried<T>, Error>
+    where
+        F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        retry_blocking(policy, func, self.clone(), Self::clone)
+    }
+
+    /// Applies the pending `migrations`, blocking the current thread until
+    /// completion.
+    ///
+    /// See [`Client::migrate`].
+    pub fn migrate_blocking(&self, migrations: &Migrations) -> Result<Vec<u64>, Error> {
+        let migrations = migrations.clone();
+        self.conn_blocking(move |conn| Ok(migrations::up(conn, &migrations)))?
+    }
+
+    /// Reverts the applied `migrations` newer than version `target`, blocking
+    /// the current thread until completion.
+    ///
+    /// See [`Client::migrate_down`].
+    pub fn migrate_down_blocking(
+        &self,
+        migrations: &Migrations,
+        target: u64,
+    ) -> Result<Vec<u64>, Error> {
+        let migrations = migrations.clone();
+        self.conn_blocking(move |conn| Ok(migrations::down(conn, &migrations, target)))?
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`], passing
+    /// the progress of the queries it runs to `on_progress` and blocking the
+    /// current thread until completion.
+    ///
+    /// `on_progress` is called from a side thread. See
+    /// [`Client::conn_with_progress`].
+    pub fn conn_with_progress_blocking<F, T, P>(&self, func: F, on_progress: P) -> Result<T, Error>
+    where
+        F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+        P: FnMut(QueryProgress) + Send + 'static,
+    {
+        self.session_blocking(move |conn, _| progress::with_progress(conn, func, on_progress))
+    }
+
+    /// Applies a duckdb setting, blocking the current thread until
+    /// completion.
+    ///
+    /// See [`Client::set_setting`].
+    pub fn set_setting_blocking<V: Display>(&self, name: &str, value: V) -> Result<(), Error> {
+        let (name, exp) = (name.to_owned(), value.to_string());
+        let (name_, exp_) = (name.clone(), exp.clone());
+        let got = self
+            .conn_blocking(move |conn| settings::set(conn, &name_, &exp_))
+            .map_err(|err| err.with_sql(settings::set_sql(&name, &exp)))?;
+        settings::verify(&name, &exp, got)
+    }
+
+    /// Returns all duckdb settings, keyed by name, blocking the current
+    /// thread until completion.
+    pub fn settings_blocking(&self) -> Result<HashMap<String, Setting>, Error> {
+        self.conn_blocking(settings::all)
+    }
+
+    /// Returns the attached databases, blocking the current thread until
+    /// completion.
+    pub fn databases_blocking(&self) -> Result<Vec<DatabaseInfo>, Error> {
+        self.conn_blocking(schema::databases)
+    }
+
+    /// Returns all schemas, blocking the current thread until completion.
+    pub fn schemas_blocking(&self) -> Result<Vec<SchemaInfo>, Error> {
+        self.conn_blocking(schema::schemas)
+    }
+
+    /// Returns all tables, blocking the current thread until completion.
+    pub fn tables_blocking(&self) -> Result<Vec<TableInfo>, Error> {
+        self.conn_blocking(schema::tables)
+    }
+
+    /// Returns all views, excluding built-in ones, blocking the current thread
+    /// until completion.
+    pub fn views_blocking(&self) -> Result<Vec<ViewInfo>, Error> {
+        self.conn_blocking(schema::views)
+    }
+
+    /// Returns the columns of all tables and views named `table`, blocking the
+    /// current thread until completion.
+    pub fn columns_blocking(&self, table: &str) -> Result<Vec<ColumnInfo>, Error> {
+        let table = table.to_owned();
+        self.conn_blocking(move |conn| schema::columns(conn, &table))
+    }
+
+    /// Returns all indexes, blocking the current thread until completion.
+    pub fn indexes_blocking(&self) -> Result<Vec<IndexInfo>, Error> {
+        self.conn_blocking(schema::indexes)
+    }
+
+    /// Returns the constraints of all tables named `table`, blocking the
+    /// current thread until completion.
+    pub fn constraints_blocking(&self, table: &str) -> Result<Vec<ConstraintInfo>, Error> {
+        let table = table.to_owned();
+        self.conn_blocking(move |conn| schema::constraints(conn, &table))
+    }
+
+    /// Returns all functions and macros, including built-in ones, blocking the
+    /// current thread until completion.
+    pub fn functions_blocking(&self) -> Result<Vec<FunctionInfo>, Error> {
+        self.conn_blocking(schema::functions)
+    }
+
+    /// Copies the result of `query` to the file at `path`, blocking the
+    /// current thread until completion.
+    ///
+    /// See [`Client::export`].
+    pub fn export_blocking<P: AsRef<Path>>(
+        &self,
+        query: &str,
+        path: P,
+        options: impl Into<ExportOptions>,
+    ) -> Result<usize, Error> {
+        let query = query.to_owned();
+        let path = path.as_ref().to_string_lossy().into_owned();
+        let options = options.into();
+        self.conn_blocking(move |conn| copy::export(conn, &query, &path, &options))
+    }
+
+    /// Copies the file at `path` into the existing `table`, blocking the
+    /// current thread until completion.
+    ///
+    /// See [`Client::import`].
+    pub fn import_blocking<P: AsRef<Path>>(
+        &self,
+        table: &str,
+        path: P,
+        options: impl Into<ImportOptions>,
+    ) -> Result<usize, Error> {
+        let table = table.to_owned();
+        let path = path.as_ref().to_string_lossy().into_owned();
+        let options = options.into();
+        self.conn_blocking(move |conn| copy::import(conn, &table, &path, &options))
+    }
+
+    /// Copies the whole database into a new database file at `path`,
+    /// blocking the current thread until completion.
+    ///
+    /// See [`Client::persist_to`].
+    pub fn persist_to_blocking<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        let path = path.as_ref().to_owned();
+        self.conn_blocking(move |conn| Ok(copy::persist_to(conn, &path)))?
+    }
+
+    /// Copies the whole database file at `path` into this database, blocking
+    /// the current thread until completion.
+    pub fn load_from_blocking<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        let path = path.as_ref().to_owned();
+        self.conn_blocking(move |conn| Ok(copy::load_from(conn, &path)))?
+    }
+
+    /// Exports the schema and data of the database into the directory `dir`,
+    /// blocking the current thread until completion.
+    ///
+    /// See [`Client::export_database`].
+    pub fn export_database_blocking<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: impl Into<ExportDatabaseOptions>,
+    ) -> Result<DatabaseCopy, Error> {
+        let dir = dir.as_ref().to_owned();
+        let options = options.into();
+        self.conn_blocking(move |conn| Ok(copy::export_database(conn, &dir, &options)))?
+    }
+
+    /// Imports a database exported into the directory `dir`, blocking the
+    /// current thread until completion.
+    pub fn import_database_blocking<P: AsRef<Path>>(&self, dir: P) -> Result<DatabaseCopy, Error> {
+        let dir = dir.as_ref().to_owned();
+        self.conn_blocking(move |conn| Ok(copy::import_database(conn, &dir)))?
+    }
+
+    /// Runs the query `sql` with `params` and collects the result into a
+    /// polars [`DataFrame`], blocking the current thread until completion.
+    ///
+    /// See [`Client::query_polars`].
+    #[cfg(feature = "polars")]
+    pub fn query_polars_blocking<P>(&self, sql: &str, params: P) -> Result<DataFrame, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        let sql = sql.to_owned();
+        self.conn_blocking(move |conn| polars::query(conn, &sql, params))
+    }
+
+    /// Writes the polars [`DataFrame`] `df` into `table` according to `mode`,
+    /// blocking the current thread until completion.
+    ///
+    /// See [`Client::write_polars`].
+    #[cfg(feature = "polars")]
+    pub fn write_polars_blocking(
+        &self,
+        table: &str,
+        df: &DataFrame,
+        mode: WriteMode,
+    ) -> Result<usize, Error> {
+        let table = table.to_owned();
+        let df = df.clone();
+        let views = self.registry.arrow_views.clone();
+        self.conn_blocking(move |conn| polars::write(conn, &views, &table, &df, mode))
+    }
+
+    /// Registers the scalar function `S` as `name`, blocking the current
+    /// thread until completion.
+    ///
+    /// See [`Client::register_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub fn register_scalar_blocking<S>(&self, name: &str) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Default,
+    {
+        self.register_blocking(name, registry::scalar::<S>(name), false)
+    }
+
+    /// Registers the scalar function `S` with a custom `state` as `name`,
+    /// blocking the current thread until completion.
+    ///
+    /// See [`Client::register_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub fn register_scalar_with_state_blocking<S>(
+        &self,
+        name: &str,
+        state: S::State,
+    ) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Clone,
+    {
+        self.register_blocking(name, registry::scalar_with_state::<S>(name, state), false)
+    }
+
+    /// Registers the async scalar function `func` as `name`, blocking the
+    /// current thread until completion.
+    ///
+    /// See [`Client::register_async_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub fn register_async_scalar_blocking<S: AsyncScalar>(
+        &self,
+        name: &str,
+        func: S,
+        options: AsyncScalarOptions,
+    ) -> Result<AsyncScalarDriver, Error> {
+        let (init, driver) = async_scalar::async_scalar(name, func, &options);
+        self.register_blocking(name, init, false)?;
+        Ok(driver)
+    }
+
+    /// Registers a table function `name` whose rows are produced by streams
+    /// of arrow record batches, blocking the current thread until completion.
+    ///
+    /// See [`Client::register_stream_table`].
+    #[cfg(feature = "vtab")]
+    pub fn register_stream_table_blocking<F, S>(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        factory: F,
+    ) -> Result<StreamTableDriver, Error>
+    where
+        F: Fn() -> S + Send + 'static,
+        S: Stream<Item = RecordBatch> + Send + 'static,
+    {
+        let (init, driver) = stream_table::stream_table(name, schema, factory);
+        self.register_blocking(name, init, false)?;
+        Ok(driver)
+    }
+
+    /// Registers in-memory arrow record `batches` as the temporary view
+    /// `name`, blocking the current thread until completion.
+    ///
+    /// See [`Client::register_arrow`].
+    #[cfg(feature = "vtab-arrow")]
+    pub fn register_arrow_blocking(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let init = self.registry.arrow_views.insert(name, schema, batches);
+        self.register_blocking(&arrow_view::key(name), init, true)
+    }
+
+    /// Drops the view `name` registered with [`Client::register_arrow`],
+    /// blocking the current thread until completion.
+    #[cfg(feature = "vtab-arrow")]
+    pub fn unregister_arrow_blocking(&self, name: &str) -> Result<(), Error> {
+        let init = self.registry.arrow_views.remove(name);
+        self.registry.remove(&arrow_view::key(name));
+        self.apply_blocking(name, init)
+    }
+
+    /// Blocking version of [`Client::register`].
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) fn register_blocking(
+        &self,
+        name: &str,
+        init: Init,
+        replace: bool,
+    ) -> Result<(), Error> {
+        let replaced = self.registry.insert(name, init.clone(), replace)?;
+        self.apply_blocking(name, init)
+            .inspect_err(|_| self.registry.restore(name, replaced))
+    }
+
+    /// Blocking version of [`Client::apply`].
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) fn apply_blocking(&self, name: &str, init: Init) -> Result<(), Error> {
+        self.conn_blocking(move |conn| init(conn))
+            .map_err(|err| Error::Registration {
+                name: name.to_owned(),
+                source: Box::new(err),
+            })
+    }
+
+    /// Closes the underlying duckdb connection, after the queued calls have
+    /// run, blocking the current thread until the worker thread has exited.
     ///
     /// After this method returns, all calls to `self::conn_blocking()` or
     /// `self::conn_mut_blocking()` will return an [`Error::Closed`] error.
     pub fn close_blocking(&self) -> Result<(), Error> {
+        self.close_with_blocking(ClosePolicy::default())
+    }
+
+    /// Closes the underlying duckdb connection according to `policy`,
+    /// blocking the current thread until the worker thread has exited.
+    ///
+    /// See [`Client::close_with`].
+    pub fn close_with_blocking(&self, policy: ClosePolicy) -> Result<(), Error> {
+        self.close_until_blocking(policy.reject_queued, policy.deadline())
+    }
+
+    /// Blocking version of [`Client::close_until`].
+    pub(crate) fn close_until_blocking(
+        &self,
+        reject_queued: bool,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        if reject_queued {
+            self.worker.reject(true);
+        }
         let (tx, rx) = bounded(1);
         let func = Box::new(move |res| _ = tx.send(res));
         if self.conn_tx.send(Command::Shutdown(func)).is_err() {
-            return Ok(());
+            return self.worker.join();
         }
+        let res = match deadline {
+            Some(deadline) => match rx.recv_deadline(deadline) {
+                Err(RecvTimeoutError::Timeout) => {
+                    self.worker.interrupt();
+                    rx.recv().ok()
+                }
+                res => res.ok(),
+            },
+            None => rx.recv().ok(),
+        };
         // If receiving fails, the connection is already closed.
-        rx.recv().unwrap_or(Ok(()))
+        res.unwrap_or(Ok(()))?;
+        self.worker.join()
+    }
+}
+
+/// Runs `sql` through `func`, attaching the SQL text, client id and elapsed
+/// time to any error.
+pub(crate) fn timed<T>(
+    client_id: u64,
+    sql: &str,
+    func: impl FnOnce() -> Result<T, duckdb::Error>,
+) -> Result<T, Error> {
+    let start = Instant::now();
+    func().map_err(|err| Error::from(err).with_query(sql, client_id, start.elapsed()))
+}
+
+/// Runs `func` on `client`, reporting the progress of the queries it runs.
+///
+/// See [`Client::conn_with_progress`].
+pub(crate) fn conn_with_progress<F, T>(
+    client: Result<Client, Error>,
+    func: F,
+) -> (
+    impl Future<Output = Result<T, Error>> + Send + use<F, T>,
+    impl Stream<Item = QueryProgress> + Send + Unpin + use<F, T>,
+)
+where
+    F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded();
+    let fut = async move {
+        client?
+            .session(move |conn, _| {
+                progress::with_progress(conn, func, move |progress| {
+                    _ = tx.unbounded_send(progress);
+                })
+            })
+            .await
+    };
+    (fut, rx)
+}
+
+/// Runs `func` on `client`, retrying transient errors according to `policy`
+/// on the client returned by `next`.
+pub(crate) async fn retry<F, T>(
+    policy: &RetryPolicy,
+    func: F,
+    mut client: Client,
+    next: impl Fn(&Client) -> Client,
+) -> Result<Retried<T>, Error>
+where
+    F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let func = Arc::new(func);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let func = func.clone();
+        match client
+            .conn_mut(move |conn| rollback_on_err(conn, &*func))
+            .await
+        {
+            Ok(value) => return Ok(Retried { value, attempts }),
+            Err(err) if attempts < policy.max_attempts && client.should_retry(policy, &err) => {
+                sleep(policy.delay(attempts)).await?;
+                client = next(&client);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Blocking version of [`retry`].
+pub(crate) fn retry_blocking<F, T>(
+    policy: &RetryPolicy,
+    func: F,
+    mut client: Client,
+    next: impl Fn(&Client) -> Client,
+) -> Result<Retried<T>, Error>
+where
+    F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let func = Arc::new(func);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let func = func.clone();
+        match client.conn_mut_blocking(move |conn| rollback_on_err(conn, &*func)) {
+            Ok(value) => return Ok(Retried { value, attempts }),
+            Err(err) if attempts < policy.max_attempts && client.should_retry(policy, &err) => {
+                thread::sleep(policy.delay(attempts));
+                client = next(&client);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Runs `func`, rolling back any transaction it left open if it fails.
+fn rollback_on_err<T>(
+    conn: &mut Connection,
+    func: impl Fn(&mut Connection) -> Result<T, duckdb::Error>,
+) -> Result<T, duckdb::Error> {
+    let res = func(conn);
+    // an aborted transaction reports autocommit, so roll back regardless;
+    // this fails harmlessly if no transaction is open
+    if res.is_err() {
+        _ = conn.execute_batch("ROLLBACK");
     }
+    res
 }
@@ -0,0 +1,147 @@
+use std::{
+    sync::{
+        Arc, Mutex, OnceLock, PoisonError,
+        atomic::{AtomicBool, Ordering::Relaxed},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use duckdb::InterruptHandle;
+use futures_channel::oneshot;
+
+use crate::Error;
+
+/// How [`Client::close_with`](crate::Client::close_with) treats the calls
+/// that are queued or running when the client is closed.
+///
+/// By default, queued calls are run before the connection is closed, and a
+/// running query is never interrupted.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use async_duckdb::{Client, ClosePolicy};
+/// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+/// let policy = ClosePolicy::new()
+///     .reject_queued(true)
+///     .grace_period(Duration::from_secs(5));
+/// client.close_with(policy).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClosePolicy {
+    pub(crate) reject_queued: bool,
+    pub(crate) grace_period: Option<Duration>,
+}
+
+impl ClosePolicy {
+    /// Returns a new [`ClosePolicy`] that runs the queued calls.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the queued calls with [`Error::Closed`] instead of running them.
+    #[must_use]
+    pub fn reject_queued(mut self, reject_queued: bool) -> Self {
+        self.reject_queued = reject_queued;
+        self
+    }
+
+    /// Interrupt the running query, and reject the queued calls, if the
+    /// connection is not closed within `grace_period`.
+    #[must_use]
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = Some(grace_period);
+        self
+    }
+
+    /// Returns when the grace period of a close starting now ends.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.grace_period
+            .map(|grace_period| Instant::now() + grace_period)
+    }
+}
+
+/// The state shared by the handles of a client and its worker thread.
+#[derive(Default)]
+pub(crate) struct Worker {
+    thread: Mutex<Option<JoinHandle<()>>>,
+    rejecting: AtomicBool,
+    interrupted: AtomicBool,
+    interrupt: OnceLock<Arc<InterruptHandle>>,
+}
+
+impl Worker {
+    pub(crate) fn set_thread(&self, thread: JoinHandle<()>) {
+        *self.thread.lock().unwrap_or_else(PoisonError::into_inner) = Some(thread);
+    }
+
+    pub(crate) fn set_interrupt(&self, interrupt: Arc<InterruptHandle>) {
+        _ = self.interrupt.set(interrupt);
+    }
+
+    /// Returns whether new and queued calls fail with [`Error::Closed`].
+    pub(crate) fn rejecting(&self) -> bool {
+        self.rejecting.load(Relaxed)
+    }
+
+    pub(crate) fn reject(&self, reject: bool) {
+        self.rejecting.store(reject, Relaxed);
+    }
+
+    /// Returns whether the running query was interrupted by [`interrupt`].
+    ///
+    /// [`interrupt`]: Worker::interrupt
+    pub(crate) fn interrupted(&self) -> bool {
+        self.interrupted.load(Relaxed)
+    }
+
+    /// Rejects the queued calls and interrupts the running query.
+    pub(crate) fn interrupt(&self) {
+        self.reject(true);
+        self.interrupted.store(true, Relaxed);
+        if let Some(interrupt) = self.interrupt.get() {
+            interrupt.interrupt();
+        }
+    }
+
+    /// Waits for the worker thread to exit, once it has closed the
+    /// connection.
+    pub(crate) fn join(&self) -> Result<(), Error> {
+        match self.take_thread() {
+            Some(thread) => thread.join().map_err(|_| Error::Closed),
+            None => Ok(()),
+        }
+    }
+
+    /// Async version of [`join`](Worker::join), which joins the worker
+    /// thread from another thread so the caller's runtime is not blocked.
+    pub(crate) async fn join_async(&self) -> Result<(), Error> {
+        let Some(thread) = self.take_thread() else {
+            return Ok(());
+        };
+        if thread.is_finished() {
+            return thread.join().map_err(|_| Error::Closed);
+        }
+        let (tx, rx) = oneshot::channel();
+        thread::Builder::new()
+            .name("async-duckdb-join".to_owned())
+            .spawn(move || _ = tx.send(thread.join().is_ok()))?;
+        if rx.await? {
+            Ok(())
+        } else {
+            Err(Error::Closed)
+        }
+    }
+
+    fn take_thread(&self) -> Option<JoinHandle<()>> {
+        self.thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+}
@@ -0,0 +1,521 @@
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::{Duration, Instant},
+};
+
+use duckdb::Connection;
+
+use crate::{
+    Error,
+    sql::{quote_ident, quote_literal, quote_qualified},
+};
+
+/// Options for [`Client::export`](crate::Client::export), selecting the file
+/// format and its settings.
+///
+/// Usually created from one of the format specific option builders:
+///
+/// ```rust
+/// # use async_duckdb::{ExportOptions, ParquetCompression, ParquetOptions};
+/// let options: ExportOptions = ParquetOptions::new()
+///     .compression(ParquetCompression::Zstd)
+///     .row_group_size(100_000)
+///     .into();
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ExportOptions {
+    /// Write a parquet file (requires the `parquet` extension).
+    Parquet(ParquetOptions),
+    /// Write a CSV file.
+    Csv(CsvOptions),
+    /// Write a JSON file (requires the `json` extension).
+    Json(JsonOptions),
+}
+
+/// Options for [`Client::export_database`](crate::Client::export_database),
+/// selecting the file format the tables are written in.
+///
+/// Unlike [`ExportOptions`], these have no partitioning settings, which
+/// `EXPORT DATABASE` rejects.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ExportDatabaseOptions {
+    /// Write parquet files (requires the `parquet` extension).
+    Parquet(DatabaseParquetOptions),
+    /// Write CSV files.
+    Csv(CsvOptions),
+    /// Write JSON files (requires the `json` extension).
+    Json(JsonOptions),
+}
+
+/// Options for [`Client::import`](crate::Client::import), selecting the file
+/// format and its settings.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ImportOptions {
+    /// Read a parquet file (requires the `parquet` extension).
+    Parquet,
+    /// Read a CSV file.
+    Csv(CsvOptions),
+    /// Read a JSON file (requires the `json` extension).
+    Json(JsonOptions),
+}
+
+/// The compression codec of an exported parquet file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Zstd,
+    Lz4,
+    Brotli,
+}
+
+impl ParquetCompression {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::Uncompressed => "uncompressed",
+            Self::Snappy => "snappy",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+            Self::Brotli => "brotli",
+        }
+    }
+}
+
+/// Settings for exporting parquet files.
+#[derive(Clone, Debug, Default)]
+pub struct ParquetOptions {
+    compression: Option<ParquetCompression>,
+    row_group_size: Option<usize>,
+    partition_by: Vec<String>,
+    per_thread_output: bool,
+}
+
+impl ParquetOptions {
+    /// Returns new [`ParquetOptions`] with duckdb's defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the compression codec.
+    #[must_use]
+    pub fn compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Specify the number of rows in each row group.
+    #[must_use]
+    pub fn row_group_size(mut self, row_group_size: usize) -> Self {
+        self.row_group_size = Some(row_group_size);
+        self
+    }
+
+    /// Write a hive partitioned directory, partitioned by the given columns.
+    #[must_use]
+    pub fn partition_by<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.partition_by = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Write one file per thread into the target directory.
+    #[must_use]
+    pub fn per_thread_output(mut self, per_thread_output: bool) -> Self {
+        self.per_thread_output = per_thread_output;
+        self
+    }
+}
+
+/// Settings for exporting a database to parquet files.
+#[derive(Clone, Debug, Default)]
+pub struct DatabaseParquetOptions {
+    compression: Option<ParquetCompression>,
+    row_group_size: Option<usize>,
+}
+
+impl DatabaseParquetOptions {
+    /// Returns new [`DatabaseParquetOptions`] with duckdb's defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the compression codec.
+    #[must_use]
+    pub fn compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Specify the number of rows in each row group.
+    #[must_use]
+    pub fn row_group_size(mut self, row_group_size: usize) -> Self {
+        self.row_group_size = Some(row_group_size);
+        self
+    }
+}
+
+/// Settings for exporting and importing CSV files.
+#[derive(Clone, Debug, Default)]
+pub struct CsvOptions {
+    delimiter: Option<char>,
+    header: Option<bool>,
+    quote: Option<char>,
+}
+
+impl CsvOptions {
+    /// Returns new [`CsvOptions`] with duckdb's defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the column delimiter.
+    #[must_use]
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Specify whether the file has a header row.
+    #[must_use]
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Specify the quote character.
+    #[must_use]
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+}
+
+/// Settings for exporting and importing JSON files.
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    array: Option<bool>,
+}
+
+impl JsonOptions {
+    /// Returns new [`JsonOptions`] with duckdb's defaults.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify whether the file is a single JSON array (`true`) or
+    /// newline-delimited JSON (`false`).
+    #[must_use]
+    pub fn array(mut self, array: bool) -> Self {
+        self.array = Some(array);
+        self
+    }
+}
+
+impl From<ParquetOptions> for ExportOptions {
+    fn from(options: ParquetOptions) -> Self {
+        Self::Parquet(options)
+    }
+}
+
+impl From<CsvOptions> for ExportOptions {
+    fn from(options: CsvOptions) -> Self {
+        Self::Csv(options)
+    }
+}
+
+impl From<JsonOptions> for ExportOptions {
+    fn from(options: JsonOptions) -> Self {
+        Self::Json(options)
+    }
+}
+
+impl From<DatabaseParquetOptions> for ExportDatabaseOptions {
+    fn from(options: DatabaseParquetOptions) -> Self {
+        Self::Parquet(options)
+    }
+}
+
+impl From<CsvOptions> for ExportDatabaseOptions {
+    fn from(options: CsvOptions) -> Self {
+        Self::Csv(options)
+    }
+}
+
+impl From<JsonOptions> for ExportDatabaseOptions {
+    fn from(options: JsonOptions) -> Self {
+        Self::Json(options)
+    }
+}
+
+impl From<CsvOptions> for ImportOptions {
+    fn from(options: CsvOptions) -> Self {
+        Self::Csv(options)
+    }
+}
+
+impl From<JsonOptions> for ImportOptions {
+    fn from(options: JsonOptions) -> Self {
+        Self::Json(options)
+    }
+}
+
+impl ExportOptions {
+    fn to_sql(&self) -> String {
+        let mut opts = Vec::new();
+        match self {
+            Self::Parquet(parquet) => {
+                opts.push("FORMAT parquet".to_owned());
+                push_parquet_sql(parquet.compression, parquet.row_group_size, &mut opts);
+                if !parquet.partition_by.is_empty() {
+                    let columns: Vec<_> = parquet
+                        .partition_by
+                        .iter()
+                        .map(|c| quote_ident(c))
+                        .collect();
+                    opts.push(format!("PARTITION_BY ({})", columns.join(", ")));
+                }
+                if parquet.per_thread_output {
+                    opts.push("PER_THREAD_OUTPUT true".to_owned());
+                }
+            }
+            Self::Csv(csv) => {
+                opts.push("FORMAT csv".to_owned());
+                csv.push_sql(&mut opts);
+            }
+            Self::Json(json) => {
+                opts.push("FORMAT json".to_owned());
+                json.push_sql(&mut opts);
+            }
+        }
+        opts.join(", ")
+    }
+}
+
+impl ExportDatabaseOptions {
+    fn to_sql(&self) -> String {
+        let mut opts = Vec::new();
+        match self {
+            Self::Parquet(parquet) => {
+                opts.push("FORMAT parquet".to_owned());
+                push_parquet_sql(parquet.compression, parquet.row_group_size, &mut opts);
+            }
+            Self::Csv(csv) => {
+                opts.push("FORMAT csv".to_owned());
+                csv.push_sql(&mut opts);
+            }
+            Self::Json(json) => {
+                opts.push("FORMAT json".to_owned());
+                json.push_sql(&mut opts);
+            }
+        }
+        opts.join(", ")
+    }
+}
+
+fn push_parquet_sql(
+    compression: Option<ParquetCompression>,
+    row_group_size: Option<usize>,
+    opts: &mut Vec<String>,
+) {
+    if let Some(compression) = compression {
+        opts.push(format!("COMPRESSION {}", compression.as_sql()));
+    }
+    if let Some(row_group_size) = row_group_size {
+        opts.push(format!("ROW_GROUP_SIZE {row_group_size}"));
+    }
+}
+
+impl ImportOptions {
+    fn to_sql(&self) -> String {
+        let mut opts = Vec::new();
+        match self {
+            Self::Parquet => opts.push("FORMAT parquet".to_owned()),
+            Self::Csv(csv) => {
+                opts.push("FORMAT csv".to_owned());
+                csv.push_sql(&mut opts);
+            }
+            Self::Json(json) => {
+                opts.push("FORMAT json".to_owned());
+                json.push_sql(&mut opts);
+            }
+        }
+        opts.join(", ")
+    }
+}
+
+impl CsvOptions {
+    fn push_sql(&self, opts: &mut Vec<String>) {
+        if let Some(delimiter) = self.delimiter {
+            opts.push(format!(
+                "DELIMITER {}",
+                quote_literal(&delimiter.to_string())
+            ));
+        }
+        if let Some(header) = self.header {
+            opts.push(format!("HEADER {header}"));
+        }
+        if let Some(quote) = self.quote {
+            opts.push(format!("QUOTE {}", quote_literal(&quote.to_string())));
+        }
+    }
+}
+
+impl JsonOptions {
+    fn push_sql(&self, opts: &mut Vec<String>) {
+        if let Some(array) = self.array {
+            opts.push(format!("ARRAY {array}"));
+        }
+    }
+}
+
+/// Copies the result of `query` to the file at `path`, returning the number
+/// of rows written.
+pub(crate) fn export(
+    conn: &Connection,
+    query: &str,
+    path: &str,
+    options: &ExportOptions,
+) -> duckdb::Result<usize> {
+    let sql = format!(
+        "COPY ({query}) TO {} ({})",
+        quote_literal(path),
+        options.to_sql()
+    );
+    conn.execute(&sql, [])
+}
+
+/// Copies the file at `path` into `table`, returning the number of rows
+/// read.
+pub(crate) fn import(
+    conn: &Connection,
+    table: &str,
+    path: &str,
+    options: &ImportOptions,
+) -> duckdb::Result<usize> {
+    let sql = format!(
+        "COPY {} FROM {} ({})",
+        quote_qualified(table),
+        quote_literal(path),
+        options.to_sql()
+    );
+    conn.execute(&sql, [])
+}
+
+/// The result of copying a whole database with
+/// [`Client::persist_to`](crate::Client::persist_to) and friends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DatabaseCopy {
+    /// The total size of the files written or read, in bytes.
+    pub bytes: u64,
+    /// How long the copy took.
+    pub duration: Duration,
+}
+
+static NEXT_ALIAS: AtomicU64 = AtomicU64::new(0);
+
+/// Copies the current database into the database file at `path`.
+pub(crate) fn persist_to(conn: &Connection, path: &Path) -> Result<DatabaseCopy, Error> {
+    let start = Instant::now();
+    attached(conn, path, false, |current, alias| {
+        conn.execute_batch(&format!("COPY FROM DATABASE {current} TO {alias}"))
+    })?;
+    Ok(DatabaseCopy {
+        bytes: fs::metadata(path)?.len(),
+        duration: start.elapsed(),
+    })
+}
+
+/// Copies the database file at `path` into the current database.
+pub(crate) fn load_from(conn: &Connection, path: &Path) -> Result<DatabaseCopy, Error> {
+    let start = Instant::now();
+    attached(conn, path, true, |current, alias| {
+        conn.execute_batch(&format!("COPY FROM DATABASE {alias} TO {current}"))
+    })?;
+    Ok(DatabaseCopy {
+        bytes: fs::metadata(path)?.len(),
+        duration: start.elapsed(),
+    })
+}
+
+/// Exports the schema and data of the current database into `dir`.
+pub(crate) fn export_database(
+    conn: &Connection,
+    dir: &Path,
+    options: &ExportDatabaseOptions,
+) -> Result<DatabaseCopy, Error> {
+    let start = Instant::now();
+    conn.execute_batch(&format!(
+        "EXPORT DATABASE {} ({})",
+        quote_literal(&dir.to_string_lossy()),
+        options.to_sql()
+    ))?;
+    Ok(DatabaseCopy {
+        bytes: dir_size(dir)?,
+        duration: start.elapsed(),
+    })
+}
+
+/// Imports a database exported into `dir` into the current database.
+pub(crate) fn import_database(conn: &Connection, dir: &Path) -> Result<DatabaseCopy, Error> {
+    let start = Instant::now();
+    conn.execute_batch(&format!(
+        "IMPORT DATABASE {}",
+        quote_literal(&dir.to_string_lossy())
+    ))?;
+    Ok(DatabaseCopy {
+        bytes: dir_size(dir)?,
+        duration: start.elapsed(),
+    })
+}
+
+/// Runs `func` with the quoted names of the current database and of the
+/// database file at `path`, attached for the duration of the call.
+fn attached(
+    conn: &Connection,
+    path: &Path,
+    read_only: bool,
+    func: impl FnOnce(&str, &str) -> duckdb::Result<()>,
+) -> duckdb::Result<()> {
+    let current: String = conn.query_row("SELECT current_database()", [], |row| row.get(0))?;
+    let alias = quote_ident(&format!(
+        "async_duckdb_copy_{}",
+        NEXT_ALIAS.fetch_add(1, Relaxed)
+    ));
+    conn.execute_batch(&format!(
+        "ATTACH {} AS {alias}{}",
+        quote_literal(&path.to_string_lossy()),
+        if read_only { " (READ_ONLY)" } else { "" }
+    ))?;
+    let res = func(&quote_ident(&current), &alias);
+    // detaching also checkpoints a written database into its file
+    let detached = conn.execute_batch(&format!("DETACH {alias}"));
+    res.and(detached)
+}
+
+/// Returns the total size of the files in `dir`.
+fn dir_size(dir: &Path) -> Result<u64, Error> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let meta = entry?.metadata()?;
+        if meta.is_file() {
+            size += meta.len();
+        }
+    }
+    Ok(size)
+}
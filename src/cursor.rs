@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use duckdb::{
+    Connection, Params,
+    arrow::{compute::concat_batches, datatypes::SchemaRef, record_batch::RecordBatch},
+};
+
+use crate::{Client, Error};
+
+/// How long a cursor may stay idle by default, see
+/// [`ClientBuilder::cursor_ttl`](crate::ClientBuilder::cursor_ttl).
+pub(crate) const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// A query result kept on the worker thread of a [`Client`], fetched in
+/// pages.
+///
+/// Created with [`Client::open_cursor`]. The query runs once, on the
+/// connection of the client, and its result is kept until fetched; fetching
+/// never runs it again, so pages stay consistent and no rows are skipped or
+/// repeated. A cursor that is not used for the
+/// [TTL](crate::ClientBuilder::cursor_ttl) expires, after which fetching
+/// fails with [`Error::CursorExpired`]. Dropping a cursor closes it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::Client;
+/// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+/// let cursor = client
+///     .open_cursor("SELECT * FROM events WHERE kind = ?", ["click"])
+///     .await?;
+/// loop {
+///     let page = cursor.fetch(100).await?;
+///     if page.num_rows() == 0 {
+///         break;
+///     }
+///     // ...
+/// }
+/// cursor.close().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cursor {
+    client: Client,
+    id: u64,
+    schema: SchemaRef,
+    column_names: Vec<String>,
+    closed: bool,
+}
+
+impl Cursor {
+    pub(crate) fn new(client: Client, id: u64, schema: SchemaRef) -> Self {
+        let column_names = schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        Self {
+            client,
+            id,
+            schema,
+            column_names,
+            closed: false,
+        }
+    }
+
+    /// Returns the id of the cursor, unique within its client.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the names of the result columns.
+    #[must_use]
+    pub fn column_names(&self) -> &[String] {
+        &self.column_names
+    }
+
+    /// Returns the arrow schema of the result.
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Returns the next `n` rows, or fewer at the end of the result. An
+    /// empty batch means the result is exhausted.
+    pub async fn fetch(&self, n: usize) -> Result<RecordBatch, Error> {
+        let id = self.id;
+        self.client
+            .session(move |_, session| session.cursors.fetch(id, n))
+            .await
+    }
+
+    /// Returns the next `n` rows, blocking the current thread.
+    ///
+    /// See [`Cursor::fetch`].
+    pub fn fetch_blocking(&self, n: usize) -> Result<RecordBatch, Error> {
+        let id = self.id;
+        self.client
+            .session_blocking(move |_, session| session.cursors.fetch(id, n))
+    }
+
+    /// Closes the cursor, releasing its result on the worker thread.
+    ///
+    /// Closing an expired cursor succeeds.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.closed = true;
+        let id = self.id;
+        self.client
+            .session(move |_, session| {
+                session.cursors.close(id);
+                Ok(())
+            })
+            .await
+    }
+
+    /// Closes the cursor, blocking the current thread.
+    ///
+    /// See [`Cursor::close`].
+    pub fn close_blocking(mut self) -> Result<(), Error> {
+        self.closed = true;
+        let id = self.id;
+        self.client.session_blocking(move |_, session| {
+            session.cursors.close(id);
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        if !self.closed {
+            let id = self.id;
+            self.client
+                .session_detached(move |_, session| session.cursors.close(id));
+        }
+    }
+}
+
+/// An open cursor on the worker thread.
+struct Open {
+    schema: SchemaRef,
+    /// The rows of the result that were not fetched yet.
+    batches: VecDeque<RecordBatch>,
+    last_used: Instant,
+}
+
+/// The open cursors of a worker thread.
+pub(crate) struct Cursors {
+    ttl: Duration,
+    next_id: u64,
+    open: HashMap<u64, Open>,
+}
+
+impl Cursors {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            next_id: 0,
+            open: HashMap::new(),
+        }
+    }
+
+    /// Runs the query `sql` and keeps its result, returning the id of the
+    /// cursor and the schema of the result.
+    pub(crate) fn open<P: Params>(
+        &mut self,
+        conn: &Connection,
+        sql: &str,
+        params: P,
+    ) -> duckdb::Result<(u64, SchemaRef)> {
+        let mut stmt = conn.prepare(sql)?;
+        let batches = stmt.query_arrow(params)?.collect();
+        let schema = stmt.schema();
+        let open = Open {
+            schema: schema.clone(),
+            batches,
+            last_used: Instant::now(),
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.insert(id, open);
+        Ok((id, schema))
+    }
+
+    /// Takes the next `n` rows of cursor `id`.
+    pub(crate) fn fetch(&mut self, id: u64, n: usize) -> Result<RecordBatch, Error> {
+        let open = self.open.get_mut(&id).ok_or(Error::CursorExpired { id })?;
+        open.last_used = Instant::now();
+        let mut page = Vec::new();
+        let mut rows = 0;
+        while rows < n
+            && let Some(batch) = open.batches.pop_front()
+        {
+            let take = batch.num_rows().min(n - rows);
+            if take < batch.num_rows() {
+                open.batches
+                    .push_front(batch.slice(take, batch.num_rows() - take));
+            }
+            page.push(batch.slice(0, take));
+            rows += take;
+        }
+        let Some(first) = page.first() else {
+            return Ok(RecordBatch::new_empty(open.schema.clone()));
+        };
+        concat_batches(&first.schema(), &page)
+            .map_err(|err| duckdb::Error::ToSqlConversionFailure(Box::new(err)).into())
+    }
+
+    /// Releases the result of cursor `id`, if it has not expired.
+    pub(crate) fn close(&mut self, id: u64) {
+        self.open.remove(&id);
+    }
+
+    /// Releases the cursors that stayed idle for the TTL.
+    pub(crate) fn expire(&mut self) {
+        let now = Instant::now();
+        self.open
+            .retain(|_, open| now.duration_since(open.last_used) < self.ttl);
+    }
+
+    /// Returns when the next cursor expires, if any is open.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.open
+            .values()
+            .map(|open| open.last_used + self.ttl)
+            .min()
+    }
+}
@@ -1,23 +1,289 @@
+use std::time::Duration;
+
+use duckdb::ffi;
+
 /// Enum of all possible errors.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
     /// Indicates that the connection to the duckdb database is closed.
     Closed,
-    /// Error updating PRAGMA.
+    /// Error updating a setting: the value read back from `duckdb_settings()`
+    /// differs from the one that was set, or is missing (`None`).
     PragmaUpdate {
-        name: &'static str,
-        exp: &'static str,
-        got: String,
+        name: String,
+        exp: String,
+        got: Option<String>,
     },
     /// Represents a [`duckdb::Error`].
     Duckdb(duckdb::Error),
+    /// Error registering a function or view on a connection.
+    Registration { name: String, source: Box<Error> },
+    /// A function of the same name was already registered on the client or
+    /// pool.
+    AlreadyRegistered { name: String },
+    /// Error applying or reverting a migration.
+    Migration { version: u64, source: Box<Error> },
+    /// An applied migration was changed or is missing from the set of
+    /// migrations.
+    MigrationDrift { version: u64, name: String },
+    /// Represents a [`std::io::Error`], e.g. reading migration files.
+    Io(std::io::Error),
+    /// An error annotated with the SQL text or label of the call that
+    /// produced it.
+    Context {
+        source: Box<Error>,
+        context: ErrorContext,
+    },
+    /// Several errors, e.g. from closing the connections of a pool.
+    Multiple(Vec<Error>),
+    /// The [`Cursor`](crate::Cursor) expired after staying idle for its TTL.
+    CursorExpired { id: u64 },
+}
+
+/// The broad category of an [`Error`].
+///
+/// The kind of a [`Error::Duckdb`] error is derived from its duckdb error
+/// type, e.g. `DUCKDB_ERROR_CONSTRAINT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// See [`Error::Closed`].
+    Closed,
+    /// See [`Error::PragmaUpdate`].
+    PragmaUpdate,
+    /// A constraint (primary key, unique, not null, check, foreign key) was
+    /// violated.
+    Constraint,
+    /// A transaction error, most commonly a write-write conflict between
+    /// concurrent transactions.
+    TransactionConflict,
+    /// The database is locked by another connection or process.
+    LockHeld,
+    /// duckdb ran out of memory.
+    OutOfMemory,
+    /// The query was interrupted.
+    Interrupted,
+    /// A catalog lookup failed, e.g. a missing table or function.
+    Catalog,
+    /// See [`Error::Registration`] and [`Error::AlreadyRegistered`].
+    Registration,
+    /// See [`Error::Migration`] and [`Error::MigrationDrift`].
+    Migration,
+    /// See [`Error::Io`].
+    Io,
+    /// See [`Error::Multiple`].
+    Multiple,
+    /// See [`Error::CursorExpired`].
+    CursorExpired,
+    /// Any other error.
+    Other,
+}
+
+/// Optional context attached to an [`Error`].
+///
+/// A context always has SQL text or a label, see [`Error::with_sql`] and
+/// [`Error::with_label`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorContext {
+    /// The SQL text being executed.
+    pub sql: Option<String>,
+    /// A label describing the failed operation.
+    pub label: Option<String>,
+    /// The id of the [`Client`](crate::Client) that produced the error.
+    pub client_id: Option<u64>,
+    /// How long the call ran on the worker before failing.
+    pub elapsed: Option<Duration>,
+}
+
+impl Error {
+    /// Returns the [`ErrorKind`] of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Closed => ErrorKind::Closed,
+            Self::PragmaUpdate { .. } => ErrorKind::PragmaUpdate,
+            Self::Duckdb(err) => classify(err),
+            Self::Registration { .. } | Self::AlreadyRegistered { .. } => ErrorKind::Registration,
+            Self::Migration { .. } | Self::MigrationDrift { .. } => ErrorKind::Migration,
+            Self::Io(_) => ErrorKind::Io,
+            Self::Context { source, .. } => source.kind(),
+            Self::Multiple(_) => ErrorKind::Multiple,
+            Self::CursorExpired { .. } => ErrorKind::CursorExpired,
+        }
+    }
+
+    /// Returns the underlying [`duckdb::Error`], if any.
+    #[must_use]
+    pub fn duckdb(&self) -> Option<&duckdb::Error> {
+        match self {
+            Self::Duckdb(err) => Some(err),
+            Self::Registration { source, .. }
+            | Self::Migration { source, .. }
+            | Self::Context { source, .. } => source.duckdb(),
+            _ => None,
+        }
+    }
+
+    /// Returns the context attached to this error, if any.
+    #[must_use]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns `Ok` if no result failed, or an [`Error::Multiple`] of all
+    /// failures.
+    pub(crate) fn collect(
+        results: impl IntoIterator<Item = Result<(), Error>>,
+    ) -> Result<(), Self> {
+        let errors: Vec<Self> = results.into_iter().filter_map(Result::err).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Self::Multiple(errors))
+        }
+    }
+
+    /// Attaches the SQL text that produced this error.
+    #[must_use]
+    pub fn with_sql<S: Into<String>>(self, sql: S) -> Self {
+        let sql = sql.into();
+        self.with_context(|ctx| ctx.sql = Some(sql))
+    }
+
+    /// Attaches a label describing the operation that produced this error.
+    #[must_use]
+    pub fn with_label<S: Into<String>>(self, label: S) -> Self {
+        let label = label.into();
+        self.with_context(|ctx| ctx.label = Some(label))
+    }
+
+    /// Attaches the SQL text that produced this error, along with the id of
+    /// the client that ran it and how long it ran.
+    pub(crate) fn with_query(self, sql: &str, client_id: u64, elapsed: Duration) -> Self {
+        self.with_context(|ctx| {
+            ctx.sql = Some(sql.to_owned());
+            ctx.client_id = Some(client_id);
+            ctx.elapsed = Some(elapsed);
+        })
+    }
+
+    fn with_context(self, func: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            Self::Context {
+                source,
+                mut context,
+            } => {
+                func(&mut context);
+                Self::Context { source, context }
+            }
+            err => {
+                let mut context = ErrorContext::default();
+                func(&mut context);
+                Self::Context {
+                    source: Box::new(err),
+                    context,
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if a constraint was violated.
+    #[must_use]
+    pub fn is_constraint_violation(&self) -> bool {
+        self.kind() == ErrorKind::Constraint
+    }
+
+    /// Returns `true` if the error is a write-write conflict between
+    /// transactions.
+    #[must_use]
+    pub fn is_transaction_conflict(&self) -> bool {
+        self.kind() == ErrorKind::TransactionConflict
+    }
+
+    /// Returns `true` if the database file is locked by another process.
+    #[must_use]
+    pub fn is_lock_held(&self) -> bool {
+        self.kind() == ErrorKind::LockHeld
+    }
+
+    /// Returns `true` if duckdb ran out of memory.
+    #[must_use]
+    pub fn is_out_of_memory(&self) -> bool {
+        self.kind() == ErrorKind::OutOfMemory
+    }
+
+    /// Returns `true` if the query was interrupted.
+    #[must_use]
+    pub fn is_interrupted(&self) -> bool {
+        self.kind() == ErrorKind::Interrupted
+    }
+
+    /// Returns `true` if a catalog lookup failed.
+    #[must_use]
+    pub fn is_catalog_error(&self) -> bool {
+        self.kind() == ErrorKind::Catalog
+    }
+}
+
+fn classify(err: &duckdb::Error) -> ErrorKind {
+    // duckdb-rs reports every failure with `ErrorCode::Unknown`, so only the
+    // message tells errors apart
+    let duckdb::Error::DuckDBFailure(_, Some(msg)) = err else {
+        return ErrorKind::Other;
+    };
+    if is_lock_failure(msg) {
+        return ErrorKind::LockHeld;
+    }
+    match error_type(msg) {
+        Some(ffi::duckdb_error_type_DUCKDB_ERROR_CONSTRAINT) => ErrorKind::Constraint,
+        Some(ffi::duckdb_error_type_DUCKDB_ERROR_CATALOG) => ErrorKind::Catalog,
+        Some(ffi::duckdb_error_type_DUCKDB_ERROR_OUT_OF_MEMORY) => ErrorKind::OutOfMemory,
+        Some(ffi::duckdb_error_type_DUCKDB_ERROR_INTERRUPT) => ErrorKind::Interrupted,
+        Some(ffi::duckdb_error_type_DUCKDB_ERROR_TRANSACTION) => ErrorKind::TransactionConflict,
+        _ => ErrorKind::Other,
+    }
+}
+
+/// Returns `true` if `msg` reports a database file locked by another process.
+///
+/// duckdb raises these as IO errors (see `LocalFileSystem::OpenFile`).
+fn is_lock_failure(msg: &str) -> bool {
+    msg.split_once(" Error: ").is_some_and(|(name, details)| {
+        name == "IO" && details.starts_with("Could not set lock on file")
+    })
+}
+
+/// Returns the duckdb error type of a failure message.
+///
+/// duckdb-rs drops the `duckdb_error_type` of a failure and keeps only the
+/// message, formatted as "<type> Error: <details>", so the type is recovered
+/// from the name duckdb gives it (see `Exception::ExceptionTypeToString`).
+fn error_type(msg: &str) -> Option<ffi::duckdb_error_type> {
+    let (name, _) = msg.split_once(" Error: ")?;
+    let ty = match name {
+        "Constraint" => ffi::duckdb_error_type_DUCKDB_ERROR_CONSTRAINT,
+        "Catalog" => ffi::duckdb_error_type_DUCKDB_ERROR_CATALOG,
+        "Out of Memory" => ffi::duckdb_error_type_DUCKDB_ERROR_OUT_OF_MEMORY,
+        "INTERRUPT" => ffi::duckdb_error_type_DUCKDB_ERROR_INTERRUPT,
+        "TransactionContext" => ffi::duckdb_error_type_DUCKDB_ERROR_TRANSACTION,
+        _ => return None,
+    };
+    Some(ty)
 }
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Duckdb(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Registration { source, .. }
+            | Self::Migration { source, .. }
+            | Self::Context { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -27,20 +293,81 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Closed => write!(f, "connection to sqlite database closed"),
-            Self::PragmaUpdate { exp, got, name } => {
+            Self::PragmaUpdate {
+                name,
+                exp,
+                got: Some(got),
+            } => {
                 write!(f, "updating pragma {name}: expected '{exp}', got '{got}'")
             }
+            Self::PragmaUpdate {
+                name, got: None, ..
+            } => {
+                write!(f, "updating pragma {name}: not found in duckdb_settings()")
+            }
             Self::Duckdb(err) => err.fmt(f),
+            Self::Registration { name, source } => write!(f, "registering {name}: {source}"),
+            Self::AlreadyRegistered { name } => write!(f, "{name} is already registered"),
+            Self::Migration { version, source } => write!(f, "migration {version}: {source}"),
+            Self::MigrationDrift { version, name } => {
+                write!(
+                    f,
+                    "applied migration {version} ({name}) was changed or removed"
+                )
+            }
+            Self::Io(err) => err.fmt(f),
+            Self::Context { source, context } if *context == ErrorContext::default() => {
+                source.fmt(f)
+            }
+            Self::Context { source, context } => write!(f, "{source} ({context})"),
+            Self::Multiple(errors) => {
+                write!(f, "{} errors", errors.len())?;
+                let mut sep = ": ";
+                for err in errors {
+                    write!(f, "{sep}{err}")?;
+                    sep = "; ";
+                }
+                Ok(())
+            }
+            Self::CursorExpired { id } => write!(f, "cursor {id} expired"),
         }
     }
 }
 
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sep = "";
+        if let Some(label) = &self.label {
+            write!(f, "{label}")?;
+            sep = ", ";
+        }
+        if let Some(id) = self.client_id {
+            write!(f, "{sep}client {id}")?;
+            sep = ", ";
+        }
+        if let Some(elapsed) = self.elapsed {
+            write!(f, "{sep}after {elapsed:?}")?;
+            sep = ", ";
+        }
+        if let Some(sql) = &self.sql {
+            write!(f, "{sep}sql: {sql}")?;
+        }
+        Ok(())
+    }
+}
+
 impl From<duckdb::Error> for Error {
     fn from(value: duckdb::Error) -> Self {
         Self::Duckdb(value)
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
 impl<T> From<crossbeam_channel::SendError<T>> for Error {
     fn from(_value: crossbeam_channel::SendError<T>) -> Self {
         Self::Closed
@@ -0,0 +1,100 @@
+use duckdb::Connection;
+
+use crate::Error;
+
+/// A write submitted with [`Client::write`](crate::Client::write), waiting
+/// to be committed together with other writes.
+pub(crate) trait WriteOp: Send {
+    /// Runs the write, keeping its result. Returns whether it succeeded.
+    fn run(&mut self, conn: &Connection) -> bool;
+
+    /// Sends the kept result, or `err` if the commit failed, to the caller.
+    fn finish(self: Box<Self>, err: Option<Error>);
+}
+
+pub(crate) struct Write<F, T> {
+    func: F,
+    res: Option<Result<T, Error>>,
+    reply: Box<dyn FnOnce(Result<T, Error>) + Send>,
+}
+
+impl<F, T> Write<F, T> {
+    pub(crate) fn new(func: F, reply: impl FnOnce(Result<T, Error>) + Send + 'static) -> Self {
+        Self {
+            func,
+            res: None,
+            reply: Box::new(reply),
+        }
+    }
+}
+
+impl<F, T> WriteOp for Write<F, T>
+where
+    F: Fn(&Connection) -> Result<T, duckdb::Error> + Send,
+    T: Send,
+{
+    fn run(&mut self, conn: &Connection) -> bool {
+        let res = (self.func)(conn).map_err(Error::from);
+        let ok = res.is_ok();
+        self.res = Some(res);
+        ok
+    }
+
+    fn finish(self: Box<Self>, err: Option<Error>) {
+        let res = match (err, self.res) {
+            (Some(err), _) => Err(err),
+            (None, Some(res)) => res,
+            (None, None) => Err(Error::Closed),
+        };
+        (self.reply)(res);
+    }
+}
+
+/// Runs `ops` in a single transaction.
+///
+/// A failing write is removed and the transaction is rolled back and retried
+/// without it, so only its own caller sees the error. If the transaction
+/// cannot be committed, every write is retried in a transaction of its own.
+pub(crate) fn commit(conn: &Connection, mut ops: Vec<Box<dyn WriteOp>>) {
+    while !ops.is_empty() {
+        if conn.execute_batch("BEGIN TRANSACTION").is_err() {
+            break;
+        }
+        if let Some(failed) = ops.iter_mut().position(|op| !op.run(conn)) {
+            _ = conn.execute_batch("ROLLBACK");
+            ops.remove(failed).finish(None);
+            continue;
+        }
+        if conn.execute_batch("COMMIT").is_ok() {
+            for op in ops {
+                op.finish(None);
+            }
+            return;
+        }
+        _ = conn.execute_batch("ROLLBACK");
+        break;
+    }
+    for op in ops {
+        commit_one(conn, op);
+    }
+}
+
+/// Runs `op` in a transaction of its own.
+fn commit_one(conn: &Connection, mut op: Box<dyn WriteOp>) {
+    if let Err(err) = conn.execute_batch("BEGIN TRANSACTION") {
+        op.finish(Some(err.into()));
+        return;
+    }
+    if !op.run(conn) {
+        _ = conn.execute_batch("ROLLBACK");
+        op.finish(None);
+        return;
+    }
+    match conn.execute_batch("COMMIT") {
+        Ok(()) => op.finish(None),
+        Err(err) => {
+            _ = conn.execute_batch("ROLLBACK");
+            op.finish(Some(err.into()));
+        }
+    }
+}
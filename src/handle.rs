@@ -0,0 +1,27 @@
+use std::{
+    mem::size_of,
+    sync::{Mutex, PoisonError},
+};
+
+use duckdb::{InterruptHandle, ffi};
+
+// `InterruptHandle` has a single field, the raw handle of its connection
+// behind a mutex. Fields do not overlap and lie within their struct, so equal
+// sizes place it at offset 0, see `with_raw`.
+const _: () = assert!(size_of::<InterruptHandle>() == size_of::<Mutex<ffi::duckdb_connection>>());
+
+/// Invokes `func` with the raw handle of the connection of `handle`, or
+/// returns `None` if the connection was closed.
+///
+/// The handle is locked while `func` runs, like `InterruptHandle::interrupt`
+/// does, so the connection cannot be closed meanwhile.
+pub(crate) fn with_raw<T>(
+    handle: &InterruptHandle,
+    func: impl FnOnce(ffi::duckdb_connection) -> T,
+) -> Option<T> {
+    // SAFETY: the mutex is the only field of `InterruptHandle`, at offset 0
+    // (see the size assertion above).
+    let conn = unsafe { &*std::ptr::from_ref(handle).cast::<Mutex<ffi::duckdb_connection>>() };
+    let conn = conn.lock().unwrap_or_else(PoisonError::into_inner);
+    (!conn.is_null()).then(|| func(*conn))
+}
@@ -0,0 +1,207 @@
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering::Relaxed},
+    },
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Receiver;
+
+use crate::{Error, ErrorKind};
+
+/// The probe used by default, see [`HealthCheck::probe`].
+const DEFAULT_PROBE: &str = "SELECT 1";
+
+/// How long a probe may take by default, see [`HealthCheck::timeout`].
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How [`Client::health`](crate::Client::health) and
+/// [`Pool::health`](crate::Pool::health) probe a worker.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use async_duckdb::{HealthCheck, PoolBuilder};
+/// # async fn run() -> Result<(), async_duckdb::Error> {
+/// let check = HealthCheck::new()
+///     .probe("SELECT count(*) FROM duckdb_tables()")
+///     .timeout(Duration::from_secs(1));
+/// let pool = PoolBuilder::new()
+///     .health_check(check)
+///     .monitor_health(Duration::from_secs(10))
+///     .open()
+///     .await?;
+/// let ready = pool.health().await.is_ready();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HealthCheck {
+    pub(crate) probe: String,
+    pub(crate) timeout: Duration,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            probe: DEFAULT_PROBE.to_owned(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl HealthCheck {
+    /// Returns a new [`HealthCheck`] running `SELECT 1` with a timeout of
+    /// five seconds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the SQL run to probe a worker.
+    #[must_use]
+    pub fn probe(mut self, sql: impl Into<String>) -> Self {
+        self.probe = sql.into();
+        self
+    }
+
+    /// Specify how long the probe may take, including the time it waits
+    /// behind queued calls, before the worker counts as unhealthy.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// The status of a worker, as seen by its last probe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HealthStatus {
+    /// The probe succeeded in time.
+    Healthy,
+    /// The probe failed or timed out.
+    Unhealthy,
+    /// The connection is closed.
+    Closed,
+}
+
+/// The health of a single worker, see [`Client::health`](crate::Client::health).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WorkerHealth {
+    /// The id of the [`Client`](crate::Client) of the worker.
+    pub client_id: u64,
+    /// The result of the probe.
+    pub status: HealthStatus,
+    /// The number of calls queued or running when the probe was sent.
+    pub queue_depth: usize,
+    /// How long the probe took, if it finished in time.
+    pub latency: Option<Duration>,
+    /// The last error of a probe on this worker, which may be from an
+    /// earlier probe.
+    pub last_error: Option<String>,
+}
+
+/// The health of the workers of a [`Pool`](crate::Pool), see
+/// [`Pool::health`](crate::Pool::health).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PoolHealth {
+    /// The health of every worker.
+    pub workers: Vec<WorkerHealth>,
+}
+
+impl PoolHealth {
+    /// Returns whether at least one worker is healthy, so the pool can serve
+    /// calls.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.workers
+            .iter()
+            .any(|worker| worker.status == HealthStatus::Healthy)
+    }
+
+    /// Returns whether every worker is healthy.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.workers
+            .iter()
+            .all(|worker| worker.status == HealthStatus::Healthy)
+    }
+}
+
+/// The health of a worker, shared by the handles of its client.
+#[derive(Default)]
+pub(crate) struct Health {
+    pub(crate) check: HealthCheck,
+    unhealthy: AtomicBool,
+    last_error: Mutex<Option<String>>,
+}
+
+/// A probe sent to a worker thread.
+pub(crate) struct Probe {
+    pub(crate) queue_depth: usize,
+    pub(crate) start: Instant,
+    pub(crate) rx: Result<Receiver<Result<(), Error>>, Error>,
+    /// Set once the probe timed out, so the worker skips it.
+    pub(crate) stale: Arc<AtomicBool>,
+}
+
+impl Health {
+    pub(crate) fn new(check: HealthCheck) -> Self {
+        Self {
+            check,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether the last probe succeeded, or no probe ran yet.
+    pub(crate) fn is_healthy(&self) -> bool {
+        !self.unhealthy.load(Relaxed)
+    }
+
+    /// Records the result of a probe, `None` if it timed out.
+    pub(crate) fn record(
+        &self,
+        client_id: u64,
+        queue_depth: usize,
+        start: Instant,
+        res: Option<Result<(), Error>>,
+    ) -> WorkerHealth {
+        let mut last_error = self
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (status, latency) = match res {
+            Some(Ok(())) => (HealthStatus::Healthy, Some(start.elapsed())),
+            Some(Err(err)) => {
+                *last_error = Some(err.to_string());
+                match err.kind() {
+                    ErrorKind::Closed => (HealthStatus::Closed, None),
+                    _ => (HealthStatus::Unhealthy, None),
+                }
+            }
+            None => {
+                *last_error = Some(format!("probe timed out after {:?}", self.check.timeout));
+                (HealthStatus::Unhealthy, None)
+            }
+        };
+        self.unhealthy
+            .store(status != HealthStatus::Healthy, Relaxed);
+        WorkerHealth {
+            client_id,
+            status,
+            queue_depth,
+            latency,
+            last_error: last_error.clone(),
+        }
+    }
+
+    /// Returns when a probe sent now times out.
+    pub(crate) fn deadline(&self) -> Instant {
+        Instant::now() + self.check.timeout
+    }
+}
@@ -46,10 +46,61 @@
 pub use duckdb;
 pub use duckdb::{Config, Connection};
 
+#[cfg(feature = "vtab-arrow")]
+mod arrow_view;
+#[cfg(feature = "vscalar")]
+mod async_scalar;
+mod batch;
 mod client;
+mod close;
+mod copy;
+mod cursor;
 mod error;
+mod group_commit;
+mod handle;
+mod health;
+mod maintenance;
+mod migrations;
+#[cfg(feature = "polars")]
+mod polars;
 mod pool;
+mod progress;
+mod registry;
+mod retry;
+mod schema;
+mod settings;
+mod sql;
+mod statement;
+#[cfg(feature = "vtab")]
+mod stream_table;
+mod timer;
+mod worker;
 
-pub use client::{Client, ClientBuilder};
-pub use error::Error;
+#[cfg(feature = "vscalar")]
+pub use async_scalar::{AsyncScalar, AsyncScalarDriver, AsyncScalarOptions};
+pub use batch::Batch;
+pub use client::{Client, ClientBuilder, Priority};
+pub use close::ClosePolicy;
+pub use copy::{
+    CsvOptions, DatabaseCopy, DatabaseParquetOptions, ExportDatabaseOptions, ExportOptions,
+    ImportOptions, JsonOptions, ParquetCompression, ParquetOptions,
+};
+pub use cursor::Cursor;
+pub use error::{Error, ErrorContext, ErrorKind};
+pub use health::{HealthCheck, HealthStatus, PoolHealth, WorkerHealth};
+pub use maintenance::{CheckpointInfo, MaintenancePolicy};
+pub use migrations::{Migration, Migrations};
+#[cfg(feature = "polars")]
+pub use polars::WriteMode;
 pub use pool::{Pool, PoolBuilder};
+pub use progress::QueryProgress;
+pub use retry::{Retried, RetryPolicy};
+pub use schema::{
+    ColumnInfo, ConstraintInfo, DatabaseInfo, FunctionInfo, IndexInfo, SchemaInfo, TableInfo,
+    ViewInfo,
+};
+pub use settings::Setting;
+pub use statement::{AsyncStatement, StatementColumn};
+#[cfg(feature = "vtab")]
+pub use stream_table::StreamTableDriver;
+pub use worker::ThreadBuilder;
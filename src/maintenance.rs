@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use duckdb::Connection;
+
+use crate::sql::quote_literal;
+
+/// How often the WAL size is checked by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When and how a [`Client`](crate::Client) checkpoints its database in the
+/// background.
+///
+/// Checkpoints run on the worker thread between calls, so they never race
+/// the writes of other calls. A failed checkpoint, e.g. because a transaction
+/// is still open, is retried at the next check.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use async_duckdb::{ClientBuilder, MaintenancePolicy};
+/// # async fn run() -> Result<(), async_duckdb::Error> {
+/// let policy = MaintenancePolicy::new()
+///     .interval(Duration::from_secs(600))
+///     .wal_size(64 * 1024 * 1024);
+/// let client = ClientBuilder::new()
+///     .path("path/to/db.duckdb")
+///     .maintenance(policy)
+///     .open()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MaintenancePolicy {
+    interval: Option<Duration>,
+    wal_size: Option<u64>,
+    poll_interval: Option<Duration>,
+    force: bool,
+    checkpoint_threshold: Option<String>,
+}
+
+impl MaintenancePolicy {
+    /// Returns a new [`MaintenancePolicy`] that does not checkpoint.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checkpoint every `interval`.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Checkpoint when the WAL file is larger than `bytes`.
+    #[must_use]
+    pub fn wal_size(mut self, bytes: u64) -> Self {
+        self.wal_size = Some(bytes);
+        self
+    }
+
+    /// Specify how often the WAL size is checked.
+    ///
+    /// Defaults to one second.
+    #[must_use]
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = Some(poll_interval);
+        self
+    }
+
+    /// Run `FORCE CHECKPOINT`, which aborts running transactions instead of
+    /// skipping the checkpoint.
+    #[must_use]
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Set duckdb's own `checkpoint_threshold` (alias `wal_autocheckpoint`),
+    /// e.g. `"256MB"`.
+    #[must_use]
+    pub fn checkpoint_threshold(mut self, size: impl Into<String>) -> Self {
+        self.checkpoint_threshold = Some(size.into());
+        self
+    }
+}
+
+/// A checkpoint run by a [`MaintenancePolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CheckpointInfo {
+    /// When the checkpoint finished.
+    pub at: SystemTime,
+    /// How long the checkpoint took.
+    pub duration: Duration,
+    /// The size of the WAL file before the checkpoint, in bytes.
+    pub wal_size: u64,
+    /// Whether `FORCE CHECKPOINT` was used.
+    pub forced: bool,
+}
+
+/// The maintenance state of a worker thread.
+pub(crate) struct Maintenance {
+    policy: MaintenancePolicy,
+    wal: Option<PathBuf>,
+    last: Arc<Mutex<Option<CheckpointInfo>>>,
+    last_at: Instant,
+    next_check: Instant,
+}
+
+impl Maintenance {
+    pub(crate) fn new(
+        policy: MaintenancePolicy,
+        path: Option<&Path>,
+        last: Arc<Mutex<Option<CheckpointInfo>>>,
+    ) -> Self {
+        let wal = path.map(|path| {
+            let mut wal = path.as_os_str().to_owned();
+            wal.push(".wal");
+            PathBuf::from(wal)
+        });
+        let now = Instant::now();
+        let mut maintenance = Self {
+            policy,
+            wal,
+            last,
+            last_at: now,
+            next_check: now,
+        };
+        maintenance.next_check = maintenance.next_check_after(now);
+        maintenance
+    }
+
+    /// Applies the duckdb settings of the policy.
+    pub(crate) fn configure(policy: &MaintenancePolicy, conn: &Connection) -> duckdb::Result<()> {
+        if let Some(size) = &policy.checkpoint_threshold {
+            conn.execute_batch(&format!(
+                "SET checkpoint_threshold = {}",
+                quote_literal(size)
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Returns when the worker should next check for a checkpoint, if ever.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        (self.policy.interval.is_some() || self.policy.wal_size.is_some())
+            .then_some(self.next_check)
+    }
+
+    /// Checkpoints `conn` if the policy says it is due.
+    pub(crate) fn run_if_due(&mut self, conn: &Connection) {
+        let now = Instant::now();
+        if self.deadline().is_none_or(|deadline| now < deadline) {
+            return;
+        }
+        let wal_size = self
+            .wal
+            .as_ref()
+            .and_then(|wal| fs::metadata(wal).ok())
+            .map_or(0, |meta| meta.len());
+        let interval_due = self
+            .policy
+            .interval
+            .is_some_and(|interval| now >= self.last_at + interval);
+        let wal_due = self.policy.wal_size.is_some_and(|max| wal_size > max);
+        if interval_due || wal_due {
+            let sql = if self.policy.force {
+                "FORCE CHECKPOINT"
+            } else {
+                "CHECKPOINT"
+            };
+            let start = Instant::now();
+            if conn.execute_batch(sql).is_ok() {
+                self.last_at = Instant::now();
+                *self.last.lock().unwrap_or_else(PoisonError::into_inner) = Some(CheckpointInfo {
+                    at: SystemTime::now(),
+                    duration: start.elapsed(),
+                    wal_size,
+                    forced: self.policy.force,
+                });
+            }
+        }
+        self.next_check = self.next_check_after(Instant::now());
+    }
+
+    fn next_check_after(&self, now: Instant) -> Instant {
+        let poll_interval = self.policy.poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+        // a failed checkpoint is retried after the poll interval
+        let interval = self
+            .policy
+            .interval
+            .map(|interval| (self.last_at + interval).max(now + poll_interval.min(interval)));
+        let poll = self.policy.wal_size.map(|_| now + poll_interval);
+        match (interval, poll) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(at), None) | (None, Some(at)) => at,
+            (None, None) => now,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    ops::Bound::{Excluded, Unbounded},
+    path::Path,
+};
+
+use duckdb::Connection;
+
+use crate::Error;
+
+/// The table recording the applied migrations.
+const TABLE: &str = "_async_duckdb_migrations";
+
+/// A single versioned schema migration.
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::Migration;
+/// let migration = Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER)")
+///     .down("DROP TABLE users");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    up: String,
+    down: Option<String>,
+}
+
+impl Migration {
+    /// Returns a new [`Migration`] that applies the `up` SQL.
+    #[must_use]
+    pub fn new(version: u64, name: impl Into<String>, up: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: up.into(),
+            down: None,
+        }
+    }
+
+    /// Specify the SQL reverting this migration.
+    ///
+    /// Migrations without it cannot be reverted by
+    /// [`Client::migrate_down`](crate::Client::migrate_down).
+    #[must_use]
+    pub fn down(mut self, down: impl Into<String>) -> Self {
+        self.down = Some(down.into());
+        self
+    }
+
+    /// Returns the version of this migration.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the name of this migration.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn checksum(&self) -> String {
+        // FNV-1a, which is stable across platforms and releases
+        let hash = self
+            .up
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{hash:016x}")
+    }
+}
+
+/// An ordered set of [`Migration`]s.
+///
+/// Applied migrations are recorded with a checksum of their SQL in the
+/// `_async_duckdb_migrations` table. Applying migrations fails with
+/// [`Error::MigrationDrift`] if an applied migration was changed or is
+/// missing from the set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::{ClientBuilder, Migration, Migrations};
+/// # async fn run() -> Result<(), async_duckdb::Error> {
+/// let migrations = Migrations::new()
+///     .migration(Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER)"))
+///     .migration(Migration::new(2, "add_name", "ALTER TABLE users ADD COLUMN name VARCHAR"));
+/// let client = ClientBuilder::new().migrations(migrations).open().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Migrations {
+    migrations: BTreeMap<u64, Migration>,
+    dry_run: bool,
+}
+
+impl Migrations {
+    /// Returns an empty set of [`Migrations`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `migration` to the set, replacing any migration with the same
+    /// version.
+    #[must_use]
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.insert(migration.version, migration);
+        self
+    }
+
+    /// Reads migrations from the `.sql` files in `dir`.
+    ///
+    /// Files are named `<version>_<name>.sql`, e.g. `0001_create_users.sql`,
+    /// with the optional down migration in `<version>_<name>.down.sql`. Other
+    /// files are ignored.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let mut ups = BTreeMap::new();
+        let mut downs = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(stem) = file_name.strip_suffix(".sql") else {
+                continue;
+            };
+            let (stem, is_down) = match stem.strip_suffix(".down") {
+                Some(stem) => (stem, true),
+                None => (stem, false),
+            };
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid migration file name {file_name}"),
+                )
+            };
+            let (version, name) = stem.split_once('_').ok_or_else(invalid)?;
+            let version: u64 = version.parse().map_err(|_| invalid())?;
+            let sql = fs::read_to_string(&path)?;
+            let files = if is_down { &mut downs } else { &mut ups };
+            if files.insert(version, (name.to_owned(), sql)).is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("duplicate migration version {version}"),
+                )
+                .into());
+            }
+        }
+        let mut migrations = Self::new();
+        for (version, (name, up)) in ups {
+            let mut migration = Migration::new(version, name, up);
+            if let Some((_, down)) = downs.remove(&version) {
+                migration = migration.down(down);
+            }
+            migrations = migrations.migration(migration);
+        }
+        if let Some(version) = downs.into_keys().next() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("down migration {version} has no up migration"),
+            )
+            .into());
+        }
+        Ok(migrations)
+    }
+
+    /// Specify whether migrations are only tried.
+    ///
+    /// In a dry run, the migrations are validated and executed as usual, but
+    /// their transaction is rolled back instead of committed, leaving the
+    /// database unchanged.
+    #[must_use]
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Applies all pending `migrations` in a single transaction, returning
+/// their versions.
+pub(crate) fn up(conn: &Connection, migrations: &Migrations) -> Result<Vec<u64>, Error> {
+    transaction(conn, migrations.dry_run, || {
+        let applied = validate(conn, migrations)?;
+        let pending: Vec<_> = migrations
+            .migrations
+            .values()
+            .filter(|migration| !applied.contains_key(&migration.version))
+            .collect();
+        for migration in &pending {
+            conn.execute_batch(&migration.up)
+                .and_then(|()| {
+                    conn.execute(
+                        &format!("INSERT INTO {TABLE} (version, name, checksum) VALUES (?, ?, ?)"),
+                        duckdb::params![migration.version, migration.name, migration.checksum()],
+                    )
+                })
+                .map_err(|err| Error::Migration {
+                    version: migration.version,
+                    source: Box::new(err.into()),
+                })?;
+        }
+        Ok(pending.iter().map(|migration| migration.version).collect())
+    })
+}
+
+/// Reverts all applied `migrations` newer than `target` in a single
+/// transaction, newest first, returning their versions.
+pub(crate) fn down(
+    conn: &Connection,
+    migrations: &Migrations,
+    target: u64,
+) -> Result<Vec<u64>, Error> {
+    transaction(conn, migrations.dry_run, || {
+        let applied = validate(conn, migrations)?;
+        let reverts: Vec<_> = applied
+            .range((Excluded(target), Unbounded))
+            .rev()
+            .filter_map(|(version, _)| migrations.migrations.get(version))
+            .collect();
+        for migration in &reverts {
+            let Some(down) = &migration.down else {
+                return Err(Error::Migration {
+                    version: migration.version,
+                    source: Box::new(
+                        io::Error::new(io::ErrorKind::Unsupported, "migration has no down SQL")
+                            .into(),
+                    ),
+                });
+            };
+            conn.execute_batch(down)
+                .and_then(|()| {
+                    conn.execute(
+                        &format!("DELETE FROM {TABLE} WHERE version = ?"),
+                        [migration.version],
+                    )
+                })
+                .map_err(|err| Error::Migration {
+                    version: migration.version,
+                    source: Box::new(err.into()),
+                })?;
+        }
+        Ok(reverts.iter().map(|migration| migration.version).collect())
+    })
+}
+
+/// Creates the migrations table if needed and checks the applied migrations
+/// against `migrations`, returning the applied versions and names.
+fn validate(conn: &Connection, migrations: &Migrations) -> Result<BTreeMap<u64, String>, Error> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {TABLE} (
+            version UBIGINT PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            checksum VARCHAR NOT NULL,
+            applied_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+        )"
+    ))?;
+    let mut stmt = conn.prepare(&format!("SELECT version, name, checksum FROM {TABLE}"))?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    let mut applied = BTreeMap::new();
+    for row in rows {
+        let (version, name, checksum): (u64, String, String) = row?;
+        match migrations.migrations.get(&version) {
+            Some(migration) if migration.checksum() == checksum => {}
+            _ => return Err(Error::MigrationDrift { version, name }),
+        }
+        applied.insert(version, name);
+    }
+    Ok(applied)
+}
+
+/// Runs `func` in a transaction that is committed on success, unless
+/// `dry_run` is set, and rolled back otherwise.
+fn transaction<T>(
+    conn: &Connection,
+    dry_run: bool,
+    func: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    conn.execute_batch("BEGIN TRANSACTION")?;
+    match func() {
+        Ok(res) => {
+            conn.execute_batch(if dry_run { "ROLLBACK" } else { "COMMIT" })?;
+            Ok(res)
+        }
+        Err(err) => {
+            _ = conn.execute_batch("ROLLBACK");
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{iter, mem::ManuallyDrop, ptr, sync::Arc};
+
+use duckdb::{
+    Connection, Params,
+    arrow::{
+        array::make_array,
+        datatypes::{Field, Schema, SchemaRef},
+        ffi::{FFI_ArrowArray, FFI_ArrowSchema, from_ffi},
+        record_batch::RecordBatch,
+    },
+    types::Type,
+};
+use polars_core::{
+    prelude::{self as pl, CompatLevel, DataFrame},
+    utils::{accumulate_dataframes_vertical_unchecked, arrow::ffi as polars_ffi},
+};
+
+use crate::{arrow_view::ArrowViews, sql::quote_ident};
+
+/// How [`Client::write_polars`](crate::Client::write_polars) writes a
+/// [`DataFrame`] into a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteMode {
+    /// Create a new table, failing if it already exists.
+    Create,
+    /// Append to an existing table, matching columns by name.
+    Append,
+    /// Create a new table, replacing any existing table of the same name.
+    Replace,
+}
+
+/// Runs `sql` and collects all result chunks into a single [`DataFrame`].
+pub(crate) fn query<P: Params>(
+    conn: &Connection,
+    sql: &str,
+    params: P,
+) -> duckdb::Result<DataFrame> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut frames = stmt.query_polars(params)?;
+    match frames.next() {
+        Some(first) => Ok(accumulate_dataframes_vertical_unchecked(
+            iter::once(first).chain(frames),
+        )),
+        None => empty_frame(&frames.get_schema()),
+    }
+}
+
+/// Builds an empty [`DataFrame`] with the columns of the arrow `schema`.
+fn empty_frame(schema: &Schema) -> duckdb::Result<DataFrame> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            empty_column(field).map_err(|err| {
+                duckdb::Error::FromSqlConversionFailure(idx, Type::from(field.data_type()), err)
+            })
+        })
+        .collect::<duckdb::Result<Vec<_>>>()?;
+    DataFrame::new(columns).map_err(|err| duckdb::Error::ToSqlConversionFailure(err.into()))
+}
+
+/// Builds an empty polars column for the arrow-rs `field`, converting its
+/// type through the arrow C data interface.
+fn empty_column(field: &Field) -> Result<pl::Column, Box<dyn std::error::Error + Send + Sync>> {
+    let ffi_schema = FFI_ArrowSchema::try_from(field)?;
+    // SAFETY: see `move_ffi`; ownership of the exported schema moves to
+    // polars.
+    let ffi_schema: polars_ffi::ArrowSchema = unsafe { move_ffi(ffi_schema) };
+    // SAFETY: the schema was exported from a valid field above.
+    let field = unsafe { polars_ffi::import_field_from_c(&ffi_schema)? };
+    let dtype = pl::DataType::from_arrow_field(&field);
+    Ok(pl::Series::new_empty(field.name, &dtype).into())
+}
+
+/// Writes `df` into `table` through an arrow scan of its columns, returning
+/// the number of rows written.
+pub(crate) fn write(
+    conn: &Connection,
+    views: &ArrowViews,
+    table: &str,
+    df: &DataFrame,
+    mode: WriteMode,
+) -> duckdb::Result<usize> {
+    let (schema, batch) = to_record_batch(df).map_err(duckdb::Error::ToSqlConversionFailure)?;
+    let table = quote_ident(table);
+    views.scoped(conn, schema, vec![batch], |scan| {
+        let sql = match mode {
+            WriteMode::Create => format!("CREATE TABLE {table} AS SELECT * FROM {scan}"),
+            WriteMode::Append => format!("INSERT INTO {table} BY NAME SELECT * FROM {scan}"),
+            WriteMode::Replace => {
+                format!("CREATE OR REPLACE TABLE {table} AS SELECT * FROM {scan}")
+            }
+        };
+        conn.execute_batch(&sql)
+    })?;
+    Ok(df.height())
+}
+
+/// Converts `df` to an arrow [`RecordBatch`] without copying its data.
+///
+/// The columns are handed from polars' arrow implementation to arrow-rs
+/// through the arrow C data interface.
+fn to_record_batch(
+    df: &DataFrame,
+) -> Result<(SchemaRef, RecordBatch), Box<dyn std::error::Error + Send + Sync>> {
+    let mut fields = Vec::with_capacity(df.width());
+    let mut columns = Vec::with_capacity(df.width());
+    for column in df.get_columns() {
+        let series = column.as_materialized_series().rechunk();
+        let field = series.field().to_arrow(CompatLevel::oldest());
+        let ffi_schema = polars_ffi::export_field_to_c(&field);
+        let ffi_array = polars_ffi::export_array_to_c(series.to_arrow(0, CompatLevel::oldest()));
+        // SAFETY: see `move_ffi`; ownership of the exported data moves to
+        // arrow-rs.
+        let (ffi_schema, ffi_array): (FFI_ArrowSchema, FFI_ArrowArray) =
+            unsafe { (move_ffi(ffi_schema), move_ffi(ffi_array)) };
+        // SAFETY: the array was exported with this schema above.
+        let data = unsafe { from_ffi(ffi_array, &ffi_schema)? };
+        fields.push(Field::try_from(&ffi_schema)?);
+        columns.push(make_array(data));
+    }
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    Ok((schema, batch))
+}
+
+/// Moves an arrow C data interface struct between its polars and arrow-rs
+/// definitions, without running the destructor of `value`.
+///
+/// # Safety
+///
+/// `T` and `U` must both be `#[repr(C)]` definitions of the same C struct,
+/// i.e. `ArrowSchema` or `ArrowArray`.
+unsafe fn move_ffi<T, U>(value: T) -> U {
+    let value = ManuallyDrop::new(value);
+    // SAFETY: guaranteed by the caller; `value` is never dropped, so the
+    // release callback runs exactly once, through the returned struct.
+    unsafe { ptr::read((&raw const *value).cast::<U>()) }
+}
@@ -1,16 +1,46 @@
 use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs,
+    future::Future,
+    io, mem,
     path::{Path, PathBuf},
     sync::{
-        Arc,
-        atomic::{AtomicU32, Ordering::Relaxed},
+        Arc, PoisonError, RwLock, Weak,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering::Relaxed},
     },
-    thread::available_parallelism,
+    thread::{self, available_parallelism},
+    time::{Duration, Instant},
 };
 
-use crate::{Client, ClientBuilder, Error};
+use crate::{
+    AsyncStatement, Batch, Client, ClientBuilder, ClosePolicy, ColumnInfo, ConstraintInfo, Cursor,
+    DatabaseCopy, DatabaseInfo, Error, ExportDatabaseOptions, ExportOptions, FunctionInfo,
+    HealthCheck, ImportOptions, IndexInfo, PoolHealth, Priority, QueryProgress, Retried,
+    RetryPolicy, SchemaInfo, Setting, TableInfo, ThreadBuilder, ViewInfo, client,
+    registry::Registry,
+};
 
+#[cfg(feature = "polars")]
+use crate::WriteMode;
+#[cfg(feature = "vtab-arrow")]
+use crate::arrow_view;
+#[cfg(any(feature = "vscalar", feature = "vtab"))]
+use crate::registry::Init;
+#[cfg(feature = "vtab")]
+use crate::stream_table::{self, StreamTableDriver};
+#[cfg(feature = "vscalar")]
+use crate::{
+    async_scalar::{self, AsyncScalar, AsyncScalarDriver, AsyncScalarOptions},
+    registry,
+};
+#[cfg(feature = "vtab")]
+use duckdb::arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
 use duckdb::{Config, Connection};
+use futures_util::Stream;
 use futures_util::future::join_all;
+#[cfg(feature = "polars")]
+use polars_core::prelude::DataFrame;
 
 /// A `PoolBuilder` can be used to create a [`Pool`] with custom
 /// configuration.
@@ -35,6 +65,21 @@ pub struct PoolBuilder {
     pub(crate) path: Option<PathBuf>,
     pub(crate) flagsfn: Option<fn() -> duckdb::Result<Config>>,
     pub(crate) num_conns: Option<usize>,
+    pub(crate) watch: Option<Duration>,
+    pub(crate) elastic: Option<Elastic>,
+    pub(crate) thread_builder: ThreadBuilder,
+    pub(crate) health_check: HealthCheck,
+    pub(crate) monitor_health: Option<Duration>,
+    pub(crate) cursor_ttl: Option<Duration>,
+    pub(crate) retry_policy: RetryPolicy,
+}
+
+/// The bounds of an elastic pool, see [`PoolBuilder::elastic`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Elastic {
+    min: usize,
+    max: usize,
+    idle_timeout: Duration,
 }
 
 impl PoolBuilder {
@@ -68,13 +113,90 @@ impl PoolBuilder {
 
     /// Specify the number of duckdb connections to open as part of the pool.
     ///
-    /// Defaults to the number of logical CPUs of the current system.
+    /// Defaults to the number of logical CPUs of the current system. Opening
+    /// the pool fails if it is 0.
     #[must_use]
     pub fn num_conns(mut self, num_conns: usize) -> Self {
         self.num_conns = Some(num_conns);
         self
     }
 
+    /// Specify the [`ThreadBuilder`] options of the worker threads.
+    ///
+    /// Worker threads are named `<name>-<index>`, counting up from 0 as
+    /// connections are opened.
+    #[must_use]
+    pub fn thread_builder(mut self, thread_builder: ThreadBuilder) -> Self {
+        self.thread_builder = thread_builder;
+        self
+    }
+
+    /// Specify the [`HealthCheck`] run by [`Pool::health`].
+    #[must_use]
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.health_check = check;
+        self
+    }
+
+    /// Probe every connection in the background every `interval`.
+    ///
+    /// Connections whose probe fails are skipped when routing calls until a
+    /// probe succeeds again, unless every connection is unhealthy. It stops
+    /// when the pool is closed or dropped.
+    #[must_use]
+    pub fn monitor_health(mut self, interval: Duration) -> Self {
+        self.monitor_health = Some(interval);
+        self
+    }
+
+    /// Specify how long a [`Cursor`] may stay idle before it expires.
+    ///
+    /// See [`ClientBuilder::cursor_ttl`].
+    #[must_use]
+    pub fn cursor_ttl(mut self, ttl: Duration) -> Self {
+        self.cursor_ttl = Some(ttl);
+        self
+    }
+
+    /// Specify the [`RetryPolicy`] returned by [`Pool::retry_policy`].
+    ///
+    /// Defaults to [`RetryPolicy::default()`].
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Let the pool grow and shrink between `min` and `max` connections.
+    ///
+    /// The pool opens `min` connections, overriding
+    /// [`PoolBuilder::num_conns`]. When every connection is busy, another one
+    /// is opened in the background, up to `max`. Connections beyond `min`
+    /// that stay idle for `idle_timeout` are closed again.
+    #[must_use]
+    pub fn elastic(mut self, min: usize, max: usize, idle_timeout: Duration) -> Self {
+        let min = min.max(1);
+        self.elastic = Some(Elastic {
+            min,
+            max: max.max(min),
+            idle_timeout,
+        });
+        self
+    }
+
+    /// Reload the pool whenever the database file changes.
+    ///
+    /// A background thread checks the modification time and size of the file
+    /// every `interval` and calls [`Pool::reload`] once a change has been
+    /// stable for one interval. It stops when the pool is closed or dropped.
+    ///
+    /// This has no effect for in-memory databases.
+    #[must_use]
+    pub fn watch(mut self, interval: Duration) -> Self {
+        self.watch = Some(interval);
+        self
+    }
+
     /// Returns a new [`Pool`] that uses the `PoolBuilder` configuration.
     ///
     /// # Examples
@@ -88,23 +210,11 @@ impl PoolBuilder {
     /// ```
     pub async fn open(self) -> Result<Pool, Error> {
         let num_conns = self.get_num_conns();
-        let opens = (0..num_conns).map(|_| {
-            ClientBuilder {
-                path: self.path.clone(),
-                flagsfn: self.flagsfn,
-            }
-            .open()
-        });
-        let clients = join_all(opens)
-            .await
-            .into_iter()
-            .collect::<Result<Vec<Client>, Error>>()?;
-        Ok(Pool {
-            state: Arc::new(State {
-                clients,
-                counter: AtomicU32::new(0),
-            }),
-        })
+        check_num_conns(num_conns)?;
+        let registry = Arc::new(Registry::default());
+        let opens = (0..num_conns).map(|index| self.client_builder(&registry, index).open());
+        let clients = open_all(opens).await?;
+        Ok(self.into_pool(clients, registry))
     }
 
     /// Returns a new [`Pool`] that uses the `PoolBuilder` configuration,
@@ -121,24 +231,64 @@ impl PoolBuilder {
     /// ```
     pub fn open_blocking(self) -> Result<Pool, Error> {
         let num_conns = self.get_num_conns();
-        let clients = (0..num_conns)
-            .map(|_| {
-                ClientBuilder {
-                    path: self.path.clone(),
-                    flagsfn: self.flagsfn,
-                }
-                .open_blocking()
-            })
-            .collect::<Result<Vec<Client>, Error>>()?;
-        Ok(Pool {
-            state: Arc::new(State {
-                clients,
-                counter: AtomicU32::new(0),
-            }),
-        })
+        check_num_conns(num_conns)?;
+        let registry = Arc::new(Registry::default());
+        let opens =
+            (0..num_conns).map(|index| self.client_builder(&registry, index).open_blocking());
+        let clients = open_all_blocking(opens)?;
+        Ok(self.into_pool(clients, registry))
+    }
+
+    fn into_pool(self, clients: Vec<Client>, registry: Arc<Registry>) -> Pool {
+        let watched = self.path.clone().zip(self.watch);
+        let elastic = self.elastic;
+        let monitor_health = self.monitor_health;
+        let next_index = AtomicUsize::new(clients.len());
+        let state = Arc::new(State {
+            clients: RwLock::new(Arc::new(clients)),
+            counter: AtomicU32::new(0),
+            registry,
+            builder: self,
+            closed: AtomicBool::new(false),
+            growing: AtomicBool::new(false),
+            next_index,
+        });
+        if let Some((path, interval)) = watched {
+            let state = Arc::downgrade(&state);
+            thread::spawn(move || watch(&state, &path, interval));
+        }
+        if let Some(elastic) = elastic {
+            let state = Arc::downgrade(&state);
+            thread::spawn(move || shrink(&state, elastic));
+        }
+        if let Some(interval) = monitor_health {
+            let state = Arc::downgrade(&state);
+            thread::spawn(move || monitor(&state, interval));
+        }
+        Pool::from_state(state)
+    }
+
+    fn client_builder(&self, registry: &Arc<Registry>, index: usize) -> ClientBuilder {
+        ClientBuilder {
+            path: self.path.clone(),
+            flagsfn: self.flagsfn,
+            registry: registry.clone(),
+            migrations: None,
+            maintenance: None,
+            group_commit: None,
+            thread_builder: self.thread_builder.clone(),
+            thread_index: Some(index),
+            close_on_drop: None,
+            health_check: self.health_check.clone(),
+            cursor_ttl: self.cursor_ttl,
+            retry_policy: self.retry_policy.clone(),
+        }
     }
 
     fn get_num_conns(&self) -> usize {
+        if let Some(elastic) = self.elastic {
+            return elastic.min;
+        }
         self.num_conns.unwrap_or_else(|| {
             match available_parallelism() {
                 Ok(n) => n.get(),
@@ -160,11 +310,111 @@ impl PoolBuilder {
 #[derive(Clone)]
 pub struct Pool {
     state: Arc<State>,
+    priority: Priority,
 }
 
 struct State {
-    clients: Vec<Client>,
+    /// The connections of the pool, never empty: opening and resizing reject
+    /// a size of 0, and an elastic pool keeps at least one connection.
+    clients: RwLock<Arc<Vec<Client>>>,
     counter: AtomicU32,
+    registry: Arc<Registry>,
+    builder: PoolBuilder,
+    closed: AtomicBool,
+    growing: AtomicBool,
+    /// The index of the next worker thread.
+    next_index: AtomicUsize,
+}
+
+/// Polls the database file at `path` and reloads the pool once a change has
+/// been stable for one `interval`.
+fn watch(state: &Weak<State>, path: &Path, interval: Duration) {
+    let stamp = || {
+        fs::metadata(path)
+            .ok()
+            .map(|meta| (meta.modified().ok(), meta.len()))
+    };
+    let mut loaded = stamp();
+    let mut seen = loaded;
+    loop {
+        thread::sleep(interval);
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        if state.closed.load(Relaxed) {
+            return;
+        }
+        let current = stamp();
+        if current != loaded && current == seen && Pool::from_state(state).reload_blocking().is_ok()
+        {
+            loaded = current;
+        }
+        seen = current;
+    }
+}
+
+/// Probes the connections of the pool every `interval`, which takes failing
+/// ones out of routing.
+fn monitor(state: &Weak<State>, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        if state.closed.load(Relaxed) {
+            return;
+        }
+        Pool::from_state(state).health_blocking();
+    }
+}
+
+/// Closes the connections of an elastic pool beyond `min` that stayed idle
+/// for `idle_timeout`.
+fn shrink(state: &Weak<State>, elastic: Elastic) {
+    let interval = (elastic.idle_timeout / 4).max(Duration::from_millis(1));
+    let mut idle_since = HashMap::new();
+    loop {
+        thread::sleep(interval);
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        if state.closed.load(Relaxed) {
+            return;
+        }
+        let pool = Pool::from_state(state);
+        let now = Instant::now();
+        let clients = pool.clients();
+        idle_since.retain(|id, _| clients.iter().any(|client| client.id() == *id));
+        let mut retire = HashSet::new();
+        for client in clients.iter().skip(elastic.min) {
+            if client.pending() > 0 {
+                idle_since.remove(&client.id());
+            } else if now.duration_since(*idle_since.entry(client.id()).or_insert(now))
+                >= elastic.idle_timeout
+            {
+                retire.insert(client.id());
+            }
+        }
+        if !retire.is_empty() {
+            _ = pool.update(|clients| {
+                let mut kept = 0;
+                clients.retain(|client| {
+                    kept += 1;
+                    kept <= elastic.min || !retire.contains(&client.id())
+                });
+            });
+        }
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("num_conns", &self.num_conns())
+            .field("priority", &self.priority)
+            .field("closed", &self.state.closed.load(Relaxed))
+            .finish_non_exhaustive()
+    }
 }
 
 impl Pool {
@@ -174,7 +424,7 @@ impl Pool {
         F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
         T: Send + 'static,
     {
-        self.get().conn(func).await
+        self.get()?.conn(func).await
     }
 
     /// Invokes the provided function with a mutable [`duckdb::Connection`].
@@ -183,18 +433,121 @@ impl Pool {
         F: FnOnce(&mut Connection) -> Result<T, duckdb::Error> + Send + 'static,
         T: Send + 'static,
     {
-        self.get().conn_mut(func).await
+        self.get()?.conn_mut(func).await
     }
 
-    /// Closes the underlying duckdb connections.
+    /// Returns a new [`Batch`] of calls that run back-to-back on one of the
+    /// connections in the pool.
     ///
-    /// After this method returns, all calls to `self::conn()` or
-    /// `self::conn_mut()` will return an [`Error::Closed`] error.
-    pub async fn close(&self) -> Result<(), Error> {
-        for client in &self.state.clients {
-            client.close().await?;
+    /// See [`Client::batch`].
+    pub fn batch<T: Send + 'static>(&self) -> Batch<T> {
+        match self.get() {
+            Ok(client) => client.batch(),
+            Err(_) => Batch::closed(),
         }
-        Ok(())
+    }
+
+    /// Runs the query `sql` with `params` on one of the connections in the
+    /// pool and returns a [`Cursor`] to fetch its result in pages.
+    ///
+    /// The cursor stays on the connection it was opened on. See
+    /// [`Client::open_cursor`].
+    pub async fn open_cursor<P>(&self, sql: &str, params: P) -> Result<Cursor, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        self.get()?.open_cursor(sql, params).await
+    }
+
+    /// Prepares `sql` on one of the connections in the pool.
+    ///
+    /// The statement stays on the connection it was prepared on. See
+    /// [`Client::prepare`].
+    pub async fn prepare(&self, sql: &str) -> Result<AsyncStatement, Error> {
+        self.get()?.prepare(sql).await
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] inside a
+    /// transaction on one of the connections in the pool.
+    ///
+    /// See [`Client::write`].
+    pub async fn write<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: Fn(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.get()?.write(func).await
+    }
+
+    /// Invokes the provided function with a mutable [`duckdb::Connection`],
+    /// retrying transient errors according to `policy`.
+    ///
+    /// Each retry runs on another connection, if the pool has more than one.
+    /// See [`Client::conn_mut_retry`].
+    pub async fn conn_mut_retry<F, T>(
+        &self,
+        policy: &RetryPolicy,
+        func: F,
+    ) -> Result<Retried<T>, Error>
+    where
+        F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        client::retry(policy, func, self.get()?, |client| self.get_other(client)).await
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] and
+    /// reports the progress of the queries it runs.
+    ///
+    /// See [`Client::conn_with_progress`].
+    pub fn conn_with_progress<F, T>(
+        &self,
+        func: F,
+    ) -> (
+        impl Future<Output = Result<T, Error>> + Send + use<F, T>,
+        impl Stream<Item = QueryProgress> + Send + Unpin + use<F, T>,
+    )
+    where
+        F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        client::conn_with_progress(self.get(), func)
+    }
+
+    /// Closes the underlying duckdb connections concurrently.
+    ///
+    /// Every connection is closed even if some fail to, in which case an
+    /// [`Error::Multiple`] of all failures is returned. After this method
+    /// returns, all calls to `self::conn()` or `self::conn_mut()` will return
+    /// an [`Error::Closed`] error.
+    pub async fn close(&self) -> Result<(), Error> {
+        self.close_with(ClosePolicy::default()).await
+    }
+
+    /// Closes the underlying duckdb connections concurrently according to
+    /// `policy`.
+    ///
+    /// The grace period is shared: running queries on every connection are
+    /// interrupted at the same deadline. See [`Client::close_with`] and
+    /// [`Pool::close`].
+    pub async fn close_with(&self, policy: ClosePolicy) -> Result<(), Error> {
+        self.state.closed.store(true, Relaxed);
+        close_all(&self.clients(), policy).await
+    }
+
+    /// Replaces the connections of the pool with freshly opened ones, e.g.
+    /// to see a database file that was rewritten by another process.
+    ///
+    /// Calls already queued on the old connections still complete; the old
+    /// connections are closed once they are no longer in use. Registered
+    /// functions and views are applied to the new connections.
+    ///
+    /// Returns an [`Error::Closed`] error if the pool was closed. If opening a
+    /// connection fails, the pool keeps its old connections.
+    pub async fn reload(&self) -> Result<(), Error> {
+        let opens = (0..self.clients().len()).map(|_| self.client_builder().open());
+        let clients = open_all(opens).await?;
+        self.replace(clients)
     }
 
     /// Invokes the provided function with a [`duckdb::Connection`], blocking
@@ -204,7 +557,26 @@ impl Pool {
         F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
         T: Send + 'static,
     {
-        self.get().conn_blocking(func)
+        self.get()?.conn_blocking(func)
+    }
+
+    /// Runs the query `sql` with `params` on one of the connections in the
+    /// pool and returns a [`Cursor`], blocking the current thread.
+    ///
+    /// See [`Pool::open_cursor`].
+    pub fn open_cursor_blocking<P>(&self, sql: &str, params: P) -> Result<Cursor, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        self.get()?.open_cursor_blocking(sql, params)
+    }
+
+    /// Prepares `sql` on one of the connections in the pool, blocking the
+    /// current thread.
+    ///
+    /// See [`Pool::prepare`].
+    pub fn prepare_blocking(&self, sql: &str) -> Result<AsyncStatement, Error> {
+        self.get()?.prepare_blocking(sql)
     }
 
     /// Invokes the provided function with a mutable [`duckdb::Connection`],
@@ -214,7 +586,47 @@ impl Pool {
         F: FnOnce(&mut Connection) -> Result<T, duckdb::Error> + Send + 'static,
         T: Send + 'static,
     {
-        self.get().conn_mut_blocking(func)
+        self.get()?.conn_mut_blocking(func)
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`] inside a
+    /// transaction on one of the connections in the pool, blocking the
+    /// current thread.
+    pub fn write_blocking<F, T>(&self, func: F) -> Result<T, Error>
+    where
+        F: Fn(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.get()?.write_blocking(func)
+    }
+
+    /// Invokes the provided function with a mutable [`duckdb::Connection`],
+    /// retrying transient errors according to `policy` and blocking the
+    /// current thread.
+    pub fn conn_mut_retry_blocking<F, T>(
+        &self,
+        policy: &RetryPolicy,
+        func: F,
+    ) -> Result<Retried<T>, Error>
+    where
+        F: Fn(&mut Connection) -> Result<T, duckdb::Error> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        client::retry_blocking(policy, func, self.get()?, |client| self.get_other(client))
+    }
+
+    /// Invokes the provided function with a [`duckdb::Connection`], passing
+    /// the progress of the queries it runs to `on_progress` and blocking the
+    /// current thread.
+    ///
+    /// See [`Client::conn_with_progress_blocking`].
+    pub fn conn_with_progress_blocking<F, T, P>(&self, func: F, on_progress: P) -> Result<T, Error>
+    where
+        F: FnOnce(&Connection) -> Result<T, duckdb::Error> + Send + 'static,
+        T: Send + 'static,
+        P: FnMut(QueryProgress) + Send + 'static,
+    {
+        self.get()?.conn_with_progress_blocking(func, on_progress)
     }
 
     /// Closes the underlying duckdb connections, blocking the current thread.
@@ -222,15 +634,243 @@ impl Pool {
     /// After this method returns, all calls to `self::conn_blocking()` or
     /// `self::conn_mut_blocking()` will return an [`Error::Closed`] error.
     pub fn close_blocking(&self) -> Result<(), Error> {
+        self.close_with_blocking(ClosePolicy::default())
+    }
+
+    /// Closes the underlying duckdb connections according to `policy`,
+    /// blocking the current thread.
+    ///
+    /// See [`Client::close_with`].
+    pub fn close_with_blocking(&self, policy: ClosePolicy) -> Result<(), Error> {
+        self.state.closed.store(true, Relaxed);
+        close_all_blocking(&self.clients(), policy)
+    }
+
+    /// Replaces the connections of the pool with freshly opened ones,
+    /// blocking the current thread.
+    ///
+    /// See [`Pool::reload`].
+    pub fn reload_blocking(&self) -> Result<(), Error> {
+        let opens = (0..self.clients().len()).map(|_| self.client_builder().open_blocking());
+        let clients = open_all_blocking(opens)?;
+        self.replace(clients)
+    }
+
+    /// Opens or closes connections until the pool has `num_conns` of them.
+    ///
+    /// Closed connections still complete the calls queued on them. With
+    /// [`PoolBuilder::elastic`], the pool keeps growing and shrinking from
+    /// the new size.
+    ///
+    /// Returns an [`Error::Closed`] error if the pool was closed, and an
+    /// [`Error::Io`] error of kind [`InvalidInput`](io::ErrorKind::InvalidInput)
+    /// if `num_conns` is 0.
+    pub async fn resize(&self, num_conns: usize) -> Result<(), Error> {
+        check_num_conns(num_conns)?;
+        let opens = (self.clients().len()..num_conns).map(|_| self.client_builder().open());
+        let opened = open_all(opens).await?;
+        self.update(|clients| resize(clients, num_conns, opened))
+    }
+
+    /// Opens or closes connections until the pool has `num_conns` of them,
+    /// blocking the current thread.
+    ///
+    /// See [`Pool::resize`].
+    pub fn resize_blocking(&self, num_conns: usize) -> Result<(), Error> {
+        check_num_conns(num_conns)?;
+        let opens =
+            (self.clients().len()..num_conns).map(|_| self.client_builder().open_blocking());
+        let opened = open_all_blocking(opens)?;
+        self.update(|clients| resize(clients, num_conns, opened))
+    }
+
+    /// Returns the current number of connections in the pool.
+    #[must_use]
+    pub fn num_conns(&self) -> usize {
+        self.clients().len()
+    }
+
+    /// Returns the connection at `index`, to run calls such as
+    /// [`Client::open_cursor`] on a specific worker.
+    ///
+    /// The indexes of connections change when the pool is reloaded or
+    /// resized.
+    #[must_use]
+    pub fn client(&self, index: usize) -> Option<Client> {
+        self.clients()
+            .get(index)
+            .map(|client| client.with_priority(self.priority))
+    }
+
+    fn replace(&self, clients: Vec<Client>) -> Result<(), Error> {
+        self.update(|current| *current = clients)
+    }
+
+    /// Swaps in the connections changed by `func`.
+    ///
+    /// Connections removed from the pool exit once their queued calls are
+    /// done.
+    fn update(&self, func: impl FnOnce(&mut Vec<Client>)) -> Result<(), Error> {
+        let mut current = self
+            .state
+            .clients
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.state.closed.load(Relaxed) {
+            return Err(Error::Closed);
+        }
+        let mut clients = Vec::clone(&current);
+        func(&mut clients);
+        let old = mem::replace(&mut *current, Arc::new(clients));
+        drop(current);
+        drop(old);
+        Ok(())
+    }
+
+    /// Opens another connection in the background if the pool is elastic,
+    /// below its maximum size and every connection is busy.
+    fn grow(&self, clients: &[Client]) {
+        let Some(elastic) = self.state.builder.elastic else {
+            return;
+        };
+        if clients.len() >= elastic.max
+            || clients.iter().any(|client| client.pending() == 0)
+            || self.state.growing.swap(true, Relaxed)
+        {
+            return;
+        }
+        let pool = self.clone();
+        thread::spawn(move || {
+            if let Ok(client) = pool.client_builder().open_blocking() {
+                _ = pool.update(|clients| {
+                    if clients.len() < elastic.max {
+                        clients.push(client);
+                    }
+                });
+            }
+            pool.state.growing.store(false, Relaxed);
+        });
+    }
+
+    fn client_builder(&self) -> ClientBuilder {
+        let index = self.state.next_index.fetch_add(1, Relaxed);
+        self.state
+            .builder
+            .client_builder(&self.state.registry, index)
+    }
+
+    fn from_state(state: Arc<State>) -> Self {
+        Self {
+            state,
+            priority: Priority::Normal,
+        }
+    }
+
+    /// Returns a snapshot of the current connections.
+    fn clients(&self) -> Arc<Vec<Client>> {
         self.state
             .clients
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns a handle to this pool whose calls have the given `priority`.
+    ///
+    /// High priority calls are sent to the least busy connection.
+    #[must_use]
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            state: self.state.clone(),
+            priority,
+        }
+    }
+
+    /// Returns a handle to this pool whose calls run before the queued calls
+    /// of normal priority, on the least busy connection.
+    ///
+    /// See [`Priority`].
+    #[must_use]
+    pub fn high_priority(&self) -> Self {
+        self.with_priority(Priority::High)
+    }
+
+    fn get(&self) -> Result<Client, Error> {
+        let clients = self.clients();
+        self.grow(&clients);
+        // unhealthy connections are skipped, unless every connection is
+        let any_healthy = clients.iter().any(Client::is_healthy);
+        let mut candidates = clients
+            .iter()
+            .filter(|client| !any_healthy || client.is_healthy());
+        let client = match self.priority {
+            Priority::High => candidates.min_by_key(|client| client.pending()),
+            Priority::Normal => {
+                let n = self.state.counter.fetch_add(1, Relaxed);
+                let len = candidates.clone().count();
+                candidates.nth(n as usize % len)
+            }
+        };
+        client
+            .map(|client| client.with_priority(self.priority))
+            .ok_or(Error::Closed)
+    }
+
+    /// Returns a connection other than `client`, unless it is the only one.
+    fn get_other(&self, client: &Client) -> Client {
+        if let Ok(other) = self.get()
+            && other.id() != client.id()
+        {
+            return other;
+        }
+        self.clients()
+            .iter()
+            .find(|other| other.id() != client.id())
+            .map_or_else(
+                || client.clone(),
+                |other| other.with_priority(self.priority),
+            )
+    }
+
+    /// Returns the [`RetryPolicy`] set with [`PoolBuilder::retry_policy`].
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.state.builder.retry_policy
+    }
+
+    /// Probes every connection in the pool concurrently with the
+    /// [`HealthCheck`] of the pool.
+    ///
+    /// See [`Client::health`].
+    pub async fn health(&self) -> PoolHealth {
+        let clients: Vec<Client> = self
+            .clients()
             .iter()
-            .try_for_each(super::client::Client::close_blocking)
+            .map(|client| client.with_priority(self.priority))
+            .collect();
+        PoolHealth {
+            workers: join_all(clients.iter().map(Client::health)).await,
+        }
     }
 
-    fn get(&self) -> &Client {
-        let n = self.state.counter.fetch_add(1, Relaxed);
-        &self.state.clients[n as usize % self.state.clients.len()]
+    /// Probes every connection in the pool concurrently with the
+    /// [`HealthCheck`] of the pool, blocking the current thread.
+    ///
+    /// See [`Client::health`].
+    pub fn health_blocking(&self) -> PoolHealth {
+        let clients: Vec<Client> = self
+            .clients()
+            .iter()
+            .map(|client| client.with_priority(self.priority))
+            .collect();
+        let probes: Vec<_> = clients.iter().map(Client::send_probe).collect();
+        let deadline = Instant::now() + self.state.builder.health_check.timeout;
+        let workers = clients
+            .iter()
+            .zip(probes)
+            .map(|(client, probe)| client.wait_probe(probe, deadline))
+            .collect();
+        PoolHealth { workers }
     }
 
     /// Runs a function on all connections in the pool asynchronously.
@@ -242,8 +882,10 @@ impl Pool {
         T: Send + 'static,
     {
         let func = Arc::new(func);
-        let futures = self.state.clients.iter().map(|client| {
+        let clients = self.clients();
+        let futures = clients.iter().map(|client| {
             let func = func.clone();
+            let client = client.with_priority(self.priority);
             async move { client.conn(move |conn| func(conn)).await }
         });
         join_all(futures).await
@@ -256,13 +898,619 @@ impl Pool {
         T: Send + 'static,
     {
         let func = Arc::new(func);
-        self.state
-            .clients
+        self.clients()
             .iter()
             .map(|client| {
                 let func = func.clone();
-                client.conn_blocking(move |conn| func(conn))
+                client
+                    .with_priority(self.priority)
+                    .conn_blocking(move |conn| func(conn))
+            })
+            .collect()
+    }
+
+    /// Applies a duckdb setting on all connections in the pool
+    /// asynchronously.
+    ///
+    /// See [`Client::set_setting`].
+    pub async fn set_setting_all<V: Display>(
+        &self,
+        name: &str,
+        value: V,
+    ) -> Vec<Result<(), Error>> {
+        let value = value.to_string();
+        let clients: Vec<Client> = self
+            .clients()
+            .iter()
+            .map(|client| client.with_priority(self.priority))
+            .collect();
+        let futures = clients
+            .iter()
+            .map(|client| client.set_setting(name, &value));
+        join_all(futures).await
+    }
+
+    /// Applies a duckdb setting on all connections in the pool, blocking the
+    /// current thread.
+    pub fn set_setting_all_blocking<V: Display>(
+        &self,
+        name: &str,
+        value: V,
+    ) -> Vec<Result<(), Error>> {
+        let value = value.to_string();
+        self.clients()
+            .iter()
+            .map(|client| {
+                client
+                    .with_priority(self.priority)
+                    .set_setting_blocking(name, &value)
             })
             .collect()
     }
+
+    /// Returns all duckdb settings of one of the connections in the pool,
+    /// keyed by name.
+    pub async fn settings(&self) -> Result<HashMap<String, Setting>, Error> {
+        self.get()?.settings().await
+    }
+
+    /// Returns the attached databases, using one of the connections in the
+    /// pool.
+    pub async fn databases(&self) -> Result<Vec<DatabaseInfo>, Error> {
+        self.get()?.databases().await
+    }
+
+    /// Returns all schemas, using one of the connections in the pool.
+    pub async fn schemas(&self) -> Result<Vec<SchemaInfo>, Error> {
+        self.get()?.schemas().await
+    }
+
+    /// Returns all tables, using one of the connections in the pool.
+    pub async fn tables(&self) -> Result<Vec<TableInfo>, Error> {
+        self.get()?.tables().await
+    }
+
+    /// Returns all views, excluding built-in ones, using one of the connections
+    /// in the pool.
+    pub async fn views(&self) -> Result<Vec<ViewInfo>, Error> {
+        self.get()?.views().await
+    }
+
+    /// Returns the columns of all tables and views named `table`, using one of
+    /// the connections in the pool.
+    pub async fn columns(&self, table: &str) -> Result<Vec<ColumnInfo>, Error> {
+        self.get()?.columns(table).await
+    }
+
+    /// Returns all indexes, using one of the connections in the pool.
+    pub async fn indexes(&self) -> Result<Vec<IndexInfo>, Error> {
+        self.get()?.indexes().await
+    }
+
+    /// Returns the constraints of all tables named `table`, using one of the
+    /// connections in the pool.
+    pub async fn constraints(&self, table: &str) -> Result<Vec<ConstraintInfo>, Error> {
+        self.get()?.constraints(table).await
+    }
+
+    /// Returns all functions and macros, including built-in ones, using one of
+    /// the connections in the pool.
+    pub async fn functions(&self) -> Result<Vec<FunctionInfo>, Error> {
+        self.get()?.functions().await
+    }
+
+    /// Returns all duckdb settings of one of the connections in the pool, keyed
+    /// by name, blocking the current thread.
+    pub fn settings_blocking(&self) -> Result<HashMap<String, Setting>, Error> {
+        self.get()?.settings_blocking()
+    }
+
+    /// Returns the attached databases, using one of the connections in the pool
+    /// and blocking the current thread.
+    pub fn databases_blocking(&self) -> Result<Vec<DatabaseInfo>, Error> {
+        self.get()?.databases_blocking()
+    }
+
+    /// Returns all schemas, using one of the connections in the pool and
+    /// blocking the current thread.
+    pub fn schemas_blocking(&self) -> Result<Vec<SchemaInfo>, Error> {
+        self.get()?.schemas_blocking()
+    }
+
+    /// Returns all tables, using one of the connections in the pool and
+    /// blocking the current thread.
+    pub fn tables_blocking(&self) -> Result<Vec<TableInfo>, Error> {
+        self.get()?.tables_blocking()
+    }
+
+    /// Returns all views, excluding built-in ones, using one of the connections
+    /// in the pool and blocking the current thread.
+    pub fn views_blocking(&self) -> Result<Vec<ViewInfo>, Error> {
+        self.get()?.views_blocking()
+    }
+
+    /// Returns the columns of all tables and views named `table`, using one of
+    /// the connections in the pool and blocking the current thread.
+    pub fn columns_blocking(&self, table: &str) -> Result<Vec<ColumnInfo>, Error> {
+        self.get()?.columns_blocking(table)
+    }
+
+    /// Returns all indexes, using one of the connections in the pool and
+    /// blocking the current thread.
+    pub fn indexes_blocking(&self) -> Result<Vec<IndexInfo>, Error> {
+        self.get()?.indexes_blocking()
+    }
+
+    /// Returns the constraints of all tables named `table`, using one of the
+    /// connections in the pool and blocking the current thread.
+    pub fn constraints_blocking(&self, table: &str) -> Result<Vec<ConstraintInfo>, Error> {
+        self.get()?.constraints_blocking(table)
+    }
+
+    /// Returns all functions and macros, including built-in ones, using one of
+    /// the connections in the pool and blocking the current thread.
+    pub fn functions_blocking(&self) -> Result<Vec<FunctionInfo>, Error> {
+        self.get()?.functions_blocking()
+    }
+
+    /// Copies the result of `query` to the file at `path` using one of the
+    /// connections in the pool, returning the number of rows written.
+    ///
+    /// See [`Client::export`].
+    pub async fn export<P: AsRef<Path>>(
+        &self,
+        query: &str,
+        path: P,
+        options: impl Into<ExportOptions>,
+    ) -> Result<usize, Error> {
+        self.get()?.export(query, path, options).await
+    }
+
+    /// Copies the file at `path` into the existing `table` using one of the
+    /// connections in the pool, returning the number of rows read.
+    ///
+    /// See [`Client::import`].
+    pub async fn import<P: AsRef<Path>>(
+        &self,
+        table: &str,
+        path: P,
+        options: impl Into<ImportOptions>,
+    ) -> Result<usize, Error> {
+        self.get()?.import(table, path, options).await
+    }
+
+    /// Copies the whole database into a new database file at `path` using
+    /// one of the connections in the pool.
+    ///
+    /// See [`Client::persist_to`].
+    pub async fn persist_to<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        self.get()?.persist_to(path).await
+    }
+
+    /// Exports the schema and data of the database into the directory `dir`
+    /// using one of the connections in the pool.
+    ///
+    /// See [`Client::export_database`].
+    pub async fn export_database<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: impl Into<ExportDatabaseOptions>,
+    ) -> Result<DatabaseCopy, Error> {
+        self.get()?.export_database(dir, options).await
+    }
+
+    /// Copies the result of `query` to the file at `path` using one of the
+    /// connections in the pool, blocking the current thread until completion.
+    pub fn export_blocking<P: AsRef<Path>>(
+        &self,
+        query: &str,
+        path: P,
+        options: impl Into<ExportOptions>,
+    ) -> Result<usize, Error> {
+        self.get()?.export_blocking(query, path, options)
+    }
+
+    /// Copies the file at `path` into the existing `table` using one of the
+    /// connections in the pool, blocking the current thread until completion.
+    pub fn import_blocking<P: AsRef<Path>>(
+        &self,
+        table: &str,
+        path: P,
+        options: impl Into<ImportOptions>,
+    ) -> Result<usize, Error> {
+        self.get()?.import_blocking(table, path, options)
+    }
+
+    /// Copies the whole database into a new database file at `path` using
+    /// one of the connections in the pool, blocking the current thread until
+    /// completion.
+    pub fn persist_to_blocking<P: AsRef<Path>>(&self, path: P) -> Result<DatabaseCopy, Error> {
+        self.get()?.persist_to_blocking(path)
+    }
+
+    /// Exports the schema and data of the database into the directory `dir`
+    /// using one of the connections in the pool, blocking the current thread
+    /// until completion.
+    pub fn export_database_blocking<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: impl Into<ExportDatabaseOptions>,
+    ) -> Result<DatabaseCopy, Error> {
+        self.get()?.export_database_blocking(dir, options)
+    }
+
+    /// Runs the query `sql` with `params` on one of the connections in the
+    /// pool and collects the result into a polars [`DataFrame`].
+    ///
+    /// See [`Client::query_polars`].
+    #[cfg(feature = "polars")]
+    pub async fn query_polars<P>(&self, sql: &str, params: P) -> Result<DataFrame, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        self.get()?.query_polars(sql, params).await
+    }
+
+    /// Writes the polars [`DataFrame`] `df` into `table` on one of the
+    /// connections in the pool, returning the number of rows written.
+    ///
+    /// See [`Client::write_polars`].
+    #[cfg(feature = "polars")]
+    pub async fn write_polars(
+        &self,
+        table: &str,
+        df: &DataFrame,
+        mode: WriteMode,
+    ) -> Result<usize, Error> {
+        self.get()?.write_polars(table, df, mode).await
+    }
+
+    /// Runs the query `sql` with `params` on one of the connections in the
+    /// pool and collects the result into a polars [`DataFrame`], blocking the
+    /// current thread until completion.
+    #[cfg(feature = "polars")]
+    pub fn query_polars_blocking<P>(&self, sql: &str, params: P) -> Result<DataFrame, Error>
+    where
+        P: duckdb::Params + Send + 'static,
+    {
+        self.get()?.query_polars_blocking(sql, params)
+    }
+
+    /// Writes the polars [`DataFrame`] `df` into `table` on one of the
+    /// connections in the pool, blocking the current thread until completion.
+    #[cfg(feature = "polars")]
+    pub fn write_polars_blocking(
+        &self,
+        table: &str,
+        df: &DataFrame,
+        mode: WriteMode,
+    ) -> Result<usize, Error> {
+        self.get()?.write_polars_blocking(table, df, mode)
+    }
+
+    /// Registers the scalar function `S` as `name` on all connections in the
+    /// pool, including ones opened later.
+    ///
+    /// See [`Client::register_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub async fn register_scalar<S>(&self, name: &str) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Default,
+    {
+        self.register(name, registry::scalar::<S>(name), false)
+            .await
+    }
+
+    /// Registers the scalar function `S` with a custom `state` as `name` on
+    /// all connections in the pool, including ones opened later.
+    #[cfg(feature = "vscalar")]
+    pub async fn register_scalar_with_state<S>(
+        &self,
+        name: &str,
+        state: S::State,
+    ) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Clone,
+    {
+        self.register(name, registry::scalar_with_state::<S>(name, state), false)
+            .await
+    }
+
+    /// Registers the scalar function `S` as `name` on all connections in the
+    /// pool, blocking the current thread.
+    #[cfg(feature = "vscalar")]
+    pub fn register_scalar_blocking<S>(&self, name: &str) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Default,
+    {
+        self.register_blocking(name, registry::scalar::<S>(name), false)
+    }
+
+    /// Registers the scalar function `S` with a custom `state` as `name` on
+    /// all connections in the pool, blocking the current thread.
+    #[cfg(feature = "vscalar")]
+    pub fn register_scalar_with_state_blocking<S>(
+        &self,
+        name: &str,
+        state: S::State,
+    ) -> Result<(), Error>
+    where
+        S: duckdb::vscalar::VScalar + 'static,
+        S::State: Clone,
+    {
+        self.register_blocking(name, registry::scalar_with_state::<S>(name, state), false)
+    }
+
+    /// Registers the async scalar function `func` as `name`.
+    ///
+    /// Each batch of input rows is sent to the returned [`AsyncScalarDriver`],
+    /// which must be spawned on the caller's runtime, while the worker thread
+    /// waits for the result.
+    ///
+    /// The function is registered on all connections in the pool, including
+    /// ones opened later.
+    #[cfg(feature = "vscalar")]
+    pub async fn register_async_scalar<S: AsyncScalar>(
+        &self,
+        name: &str,
+        func: S,
+        options: AsyncScalarOptions,
+    ) -> Result<AsyncScalarDriver, Error> {
+        let (init, driver) = async_scalar::async_scalar(name, func, &options);
+        self.register(name, init, false).await?;
+        Ok(driver)
+    }
+
+    /// Registers a table function `name` whose rows are produced by streams
+    /// of arrow record batches with the given `schema`.
+    ///
+    /// Every scan of the table, e.g. `SELECT * FROM name()`, calls `factory`
+    /// to create a new stream. The streams are polled by the returned
+    /// [`StreamTableDriver`], which must be spawned on the caller's runtime.
+    /// Queries scanning the table must not block the thread that polls the
+    /// driver, see [`StreamTableDriver`].
+    ///
+    /// The table function is registered on all connections in the pool,
+    /// including ones opened later.
+    #[cfg(feature = "vtab")]
+    pub async fn register_stream_table<F, S>(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        factory: F,
+    ) -> Result<StreamTableDriver, Error>
+    where
+        F: Fn() -> S + Send + 'static,
+        S: Stream<Item = RecordBatch> + Send + 'static,
+    {
+        let (init, driver) = stream_table::stream_table(name, schema, factory);
+        self.register(name, init, false).await?;
+        Ok(driver)
+    }
+
+    /// Registers in-memory arrow record `batches` as the temporary view
+    /// `name` on all connections in the pool, including ones opened later.
+    ///
+    /// The batches are shared by all connections and scanned in place.
+    #[cfg(feature = "vtab-arrow")]
+    pub async fn register_arrow(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let init = self
+            .state
+            .registry
+            .arrow_views
+            .insert(name, schema, batches);
+        self.register(&arrow_view::key(name), init, true).await
+    }
+
+    /// Drops the view `name` registered with [`Pool::register_arrow`] from
+    /// all connections and releases its batches.
+    #[cfg(feature = "vtab-arrow")]
+    pub async fn unregister_arrow(&self, name: &str) -> Result<(), Error> {
+        let init = self.state.registry.arrow_views.remove(name);
+        self.state.registry.remove(&arrow_view::key(name));
+        self.apply(name, init).await
+    }
+
+    /// Registers the async scalar function `func` as `name`, blocking the
+    /// current thread until completion.
+    ///
+    /// See [`Pool::register_async_scalar`].
+    #[cfg(feature = "vscalar")]
+    pub fn register_async_scalar_blocking<S: AsyncScalar>(
+        &self,
+        name: &str,
+        func: S,
+        options: AsyncScalarOptions,
+    ) -> Result<AsyncScalarDriver, Error> {
+        let (init, driver) = async_scalar::async_scalar(name, func, &options);
+        self.register_blocking(name, init, false)?;
+        Ok(driver)
+    }
+
+    /// Registers a table function `name` whose rows are produced by streams
+    /// of arrow record batches, blocking the current thread until completion.
+    ///
+    /// See [`Pool::register_stream_table`].
+    #[cfg(feature = "vtab")]
+    pub fn register_stream_table_blocking<F, S>(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        factory: F,
+    ) -> Result<StreamTableDriver, Error>
+    where
+        F: Fn() -> S + Send + 'static,
+        S: Stream<Item = RecordBatch> + Send + 'static,
+    {
+        let (init, driver) = stream_table::stream_table(name, schema, factory);
+        self.register_blocking(name, init, false)?;
+        Ok(driver)
+    }
+
+    /// Registers in-memory arrow record `batches` as the temporary view
+    /// `name` on all connections, blocking the current thread until
+    /// completion.
+    ///
+    /// See [`Pool::register_arrow`].
+    #[cfg(feature = "vtab-arrow")]
+    pub fn register_arrow_blocking(
+        &self,
+        name: &str,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<(), Error> {
+        let init = self
+            .state
+            .registry
+            .arrow_views
+            .insert(name, schema, batches);
+        self.register_blocking(&arrow_view::key(name), init, true)
+    }
+
+    /// Drops the view `name` registered with [`Pool::register_arrow`] from
+    /// all connections, blocking the current thread until completion.
+    #[cfg(feature = "vtab-arrow")]
+    pub fn unregister_arrow_blocking(&self, name: &str) -> Result<(), Error> {
+        let init = self.state.registry.arrow_views.remove(name);
+        self.state.registry.remove(&arrow_view::key(name));
+        self.apply_blocking(name, &init)
+    }
+
+    /// Records `init` in the shared registry and applies it to all
+    /// connections, rolling the registry back unless it succeeded on every
+    /// one of them.
+    ///
+    /// See [`Client::register`].
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    async fn register(&self, name: &str, init: Init, replace: bool) -> Result<(), Error> {
+        let replaced = self.state.registry.insert(name, init.clone(), replace)?;
+        self.apply(name, init)
+            .await
+            .inspect_err(|_| self.state.registry.restore(name, replaced))
+    }
+
+    /// Applies `init` to all connections without recording it.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    async fn apply(&self, name: &str, init: Init) -> Result<(), Error> {
+        let clients = self.clients();
+        let futures = clients
+            .iter()
+            .map(|client| client.apply(name, init.clone()));
+        join_all(futures)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<()>, Error>>()?;
+        Ok(())
+    }
+
+    /// Blocking version of [`Pool::register`].
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    fn register_blocking(&self, name: &str, init: Init, replace: bool) -> Result<(), Error> {
+        let applied = Arc::clone(&init);
+        let replaced = self.state.registry.insert(name, init, replace)?;
+        self.apply_blocking(name, &applied)
+            .inspect_err(|_| self.state.registry.restore(name, replaced))
+    }
+
+    /// Blocking version of [`Pool::apply`].
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    fn apply_blocking(&self, name: &str, init: &Init) -> Result<(), Error> {
+        self.clients()
+            .iter()
+            .try_for_each(|client| client.apply_blocking(name, init.clone()))
+    }
+}
+
+/// Rejects a pool size of 0, which would leave no connection to run calls on.
+fn check_num_conns(num_conns: usize) -> Result<(), Error> {
+    if num_conns == 0 {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a pool needs at least one connection",
+        )));
+    }
+    Ok(())
+}
+
+/// Truncates `clients` to `num_conns`, or fills it up with `opened`.
+fn resize(clients: &mut Vec<Client>, num_conns: usize, opened: Vec<Client>) {
+    clients.truncate(num_conns);
+    let missing = num_conns - clients.len();
+    clients.extend(opened.into_iter().take(missing));
+}
+
+/// Opens connections concurrently. If any fails to open, the others are
+/// closed again and the first error is returned.
+async fn open_all<F>(opens: impl IntoIterator<Item = F>) -> Result<Vec<Client>, Error>
+where
+    F: Future<Output = Result<Client, Error>>,
+{
+    let mut clients = Vec::new();
+    let mut failed = None;
+    for res in join_all(opens).await {
+        match res {
+            Ok(client) => clients.push(client),
+            Err(err) => _ = failed.get_or_insert(err),
+        }
+    }
+    if let Some(err) = failed {
+        // the open error is the one worth reporting
+        _ = close_all(&clients, ClosePolicy::default()).await;
+        return Err(err);
+    }
+    Ok(clients)
+}
+
+/// Opens connections one after another. If one fails to open, the ones
+/// opened before are closed again.
+fn open_all_blocking(
+    opens: impl IntoIterator<Item = Result<Client, Error>>,
+) -> Result<Vec<Client>, Error> {
+    let mut clients = Vec::new();
+    for res in opens {
+        match res {
+            Ok(client) => clients.push(client),
+            Err(err) => {
+                _ = close_all_blocking(&clients, ClosePolicy::default());
+                return Err(err);
+            }
+        }
+    }
+    Ok(clients)
+}
+
+/// Closes `clients` concurrently, with a shared grace period.
+async fn close_all(clients: &[Client], policy: ClosePolicy) -> Result<(), Error> {
+    let deadline = policy.deadline();
+    let closes = clients
+        .iter()
+        .map(|client| client.close_until(policy.reject_queued, deadline));
+    Error::collect(join_all(closes).await)
+}
+
+/// Closes `clients` concurrently from scoped threads, with a shared grace
+/// period.
+fn close_all_blocking(clients: &[Client], policy: ClosePolicy) -> Result<(), Error> {
+    let deadline = policy.deadline();
+    thread::scope(|scope| {
+        let closes: Vec<_> = clients
+            .iter()
+            .map(|client| {
+                scope.spawn(move || client.close_until_blocking(policy.reject_queued, deadline))
+            })
+            .collect();
+        Error::collect(
+            closes
+                .into_iter()
+                .map(|close| close.join().unwrap_or(Err(Error::Closed))),
+        )
+    })
 }
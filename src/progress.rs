@@ -0,0 +1,135 @@
+use std::{
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, unbounded};
+use duckdb::{Connection, InterruptHandle, ffi};
+
+use crate::{Error, handle};
+
+/// How often the progress of a running query is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The progress of a running query, as reported by duckdb.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct QueryProgress {
+    /// The estimated completion, between `0.0` and `100.0`.
+    pub percentage: f64,
+    /// The number of rows processed so far.
+    pub rows_processed: u64,
+    /// The estimated total number of rows to process.
+    pub total_rows: u64,
+}
+
+/// A connection whose progress is polled by the progress thread.
+struct Watch {
+    handle: Arc<InterruptHandle>,
+    /// Cleared once the watched call returned, and held while polling so
+    /// that the connection is not polled afterwards.
+    active: Arc<Mutex<bool>>,
+    report: Box<dyn FnMut(QueryProgress) + Send>,
+    last: Option<QueryProgress>,
+}
+
+impl Watch {
+    /// Reports the progress of the connection if it changed, returning
+    /// `false` once the watched call returned.
+    fn poll(&mut self) -> bool {
+        let active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        if !*active {
+            return false;
+        }
+        if let Some(progress) = query_progress(&self.handle)
+            && self.last != Some(progress)
+        {
+            self.last = Some(progress);
+            (self.report)(progress);
+        }
+        true
+    }
+}
+
+static WATCHES: OnceLock<Sender<Watch>> = OnceLock::new();
+
+/// Runs `func` on `conn` while the progress thread passes the progress of
+/// the running query to `report` whenever it changes.
+///
+/// The progress bar is enabled (without printing) for the duration of
+/// `func` and restored afterwards.
+pub(crate) fn with_progress<T>(
+    conn: &Connection,
+    func: impl FnOnce(&Connection) -> Result<T, duckdb::Error>,
+    report: impl FnMut(QueryProgress) + Send + 'static,
+) -> Result<T, Error> {
+    let watches = watches()?;
+    let (enabled, print): (bool, bool) = conn.query_row(
+        "SELECT current_setting('enable_progress_bar'), current_setting('enable_progress_bar_print')",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    conn.execute_batch("SET enable_progress_bar = true; SET enable_progress_bar_print = false")?;
+
+    let active = Arc::new(Mutex::new(true));
+    let watch = Watch {
+        handle: conn.interrupt_handle(),
+        active: Arc::clone(&active),
+        report: Box::new(report),
+        last: None,
+    };
+    _ = watches.send(watch);
+    let res = func(conn);
+    *active.lock().unwrap_or_else(PoisonError::into_inner) = false;
+
+    // a failed restore, e.g. in an aborted transaction, must not hide the
+    // result of `func`
+    _ = conn.execute_batch(&format!(
+        "SET enable_progress_bar = {enabled}; SET enable_progress_bar_print = {print}"
+    ));
+    Ok(res?)
+}
+
+/// Returns the channel of the progress thread, starting it on first use.
+fn watches() -> Result<&'static Sender<Watch>, Error> {
+    if let Some(watches) = WATCHES.get() {
+        return Ok(watches);
+    }
+    let (tx, rx) = unbounded();
+    thread::Builder::new()
+        .name("async-duckdb-progress".to_owned())
+        .spawn(move || run(&rx))?;
+    // a thread started concurrently exits once its channel is dropped
+    Ok(WATCHES.get_or_init(|| tx))
+}
+
+fn run(rx: &Receiver<Watch>) {
+    let mut watches: Vec<Watch> = Vec::new();
+    loop {
+        let next = if watches.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(POLL_INTERVAL)
+        };
+        match next {
+            Ok(watch) => watches.push(watch),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        watches.retain_mut(Watch::poll);
+    }
+}
+
+/// Returns the progress of the query running on the connection of `handle`,
+/// if any.
+fn query_progress(handle: &InterruptHandle) -> Option<QueryProgress> {
+    // SAFETY: the connection is open while its call is watched, and duckdb
+    // allows querying the progress while another thread runs a query on it.
+    let progress = handle::with_raw(handle, |conn| unsafe { ffi::duckdb_query_progress(conn) })?;
+    (progress.percentage >= 0.0).then_some(QueryProgress {
+        percentage: progress.percentage,
+        rows_processed: progress.rows_processed,
+        total_rows: progress.total_rows_to_process,
+    })
+}
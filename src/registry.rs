@@ -0,0 +1,102 @@
+#[cfg(any(feature = "vscalar", feature = "vtab"))]
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use duckdb::Connection;
+
+#[cfg(any(feature = "vscalar", feature = "vtab"))]
+use crate::Error;
+#[cfg(feature = "vtab-arrow")]
+use crate::arrow_view::ArrowViews;
+
+/// A function that registers something (e.g. a scalar function) on a
+/// connection.
+pub(crate) type Init = Arc<dyn Fn(&Connection) -> duckdb::Result<()> + Send + Sync>;
+
+/// Named registrations that are applied to every connection opened by a
+/// [`Client`](crate::Client) or [`Pool`](crate::Pool), including ones opened
+/// after the registration was made.
+#[derive(Default)]
+pub(crate) struct Registry {
+    entries: Mutex<Vec<(String, Init)>>,
+    #[cfg(feature = "vtab-arrow")]
+    pub(crate) arrow_views: ArrowViews,
+}
+
+impl Registry {
+    /// Records `init` under `name`, returning the entry it replaced.
+    ///
+    /// Unless `replace` is set, fails with [`Error::AlreadyRegistered`] if
+    /// `name` is taken.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) fn insert(
+        &self,
+        name: &str,
+        init: Init,
+        replace: bool,
+    ) -> Result<Option<Init>, Error> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        match entries.iter_mut().find(|(n, _)| n == name) {
+            Some(_) if !replace => Err(Error::AlreadyRegistered {
+                name: name.to_owned(),
+            }),
+            Some((_, entry)) => Ok(Some(mem::replace(entry, init))),
+            None => {
+                entries.push((name.to_owned(), init));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Rolls back an [`insert`](Registry::insert) of `name`, restoring the
+    /// entry it replaced.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    pub(crate) fn restore(&self, name: &str, replaced: Option<Init>) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        match replaced {
+            Some(init) => {
+                if let Some((_, entry)) = entries.iter_mut().find(|(n, _)| n == name) {
+                    *entry = init;
+                }
+            }
+            None => entries.retain(|(n, _)| n != name),
+        }
+    }
+
+    /// Removes the entry recorded under `name`, if any.
+    #[cfg(any(feature = "vscalar", feature = "vtab"))]
+    #[cfg_attr(not(feature = "vtab-arrow"), expect(dead_code))]
+    pub(crate) fn remove(&self, name: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.retain(|(n, _)| n != name);
+    }
+
+    /// Applies all recorded entries to `conn`, in registration order.
+    pub(crate) fn apply(&self, conn: &Connection) -> duckdb::Result<()> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.iter().try_for_each(|(_, init)| init(conn))
+    }
+}
+
+/// Returns an [`Init`] that registers the scalar function `S` as `name`.
+#[cfg(feature = "vscalar")]
+pub(crate) fn scalar<S>(name: &str) -> Init
+where
+    S: duckdb::vscalar::VScalar + 'static,
+    S::State: Default,
+{
+    let name = name.to_owned();
+    Arc::new(move |conn| conn.register_scalar_function::<S>(&name))
+}
+
+/// Returns an [`Init`] that registers the scalar function `S` with `state`
+/// as `name`.
+#[cfg(feature = "vscalar")]
+pub(crate) fn scalar_with_state<S>(name: &str, state: S::State) -> Init
+where
+    S: duckdb::vscalar::VScalar + 'static,
+    S::State: Clone,
+{
+    let name = name.to_owned();
+    Arc::new(move |conn| conn.register_scalar_function_with_state::<S>(&name, &state))
+}
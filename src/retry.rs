@@ -0,0 +1,151 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    time::{Duration, Instant},
+};
+
+use crate::{Error, ErrorKind};
+
+/// A `RetryPolicy` controls how [`Client::conn_mut_retry`] retries
+/// transient errors.
+///
+/// Only transaction conflicts, held locks, and queries interrupted because
+/// their connection was closing, are retried. See
+/// [`RetryPolicy::should_retry`].
+///
+/// A default policy for a client or pool can be set with
+/// [`ClientBuilder::retry_policy`] and [`PoolBuilder::retry_policy`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::RetryPolicy;
+/// # use std::time::Duration;
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .backoff(Duration::from_millis(20))
+///     .jitter(0.2);
+/// ```
+///
+/// [`Client::conn_mut_retry`]: crate::Client::conn_mut_retry
+/// [`ClientBuilder::retry_policy`]: crate::ClientBuilder::retry_policy
+/// [`PoolBuilder::retry_policy`]: crate::PoolBuilder::retry_policy
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    pub(crate) backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) multiplier: f64,
+    pub(crate) jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a new [`RetryPolicy`] with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the maximum number of attempts, including the first one.
+    ///
+    /// Defaults to 3.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Specify the delay before the first retry.
+    ///
+    /// Defaults to 10ms.
+    #[must_use]
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Specify the upper bound for the delay between retries.
+    ///
+    /// Defaults to 1s.
+    #[must_use]
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Specify the factor the delay grows by after each retry.
+    ///
+    /// Defaults to 2.0.
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Specify the fraction of each delay that is randomized, between 0.0
+    /// and 1.0.
+    ///
+    /// Defaults to 0.5.
+    #[must_use]
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns `true` if `err` is a transaction conflict or held lock, that
+    /// may succeed when retried.
+    ///
+    /// Interrupted queries are only retried when their connection was closing,
+    /// which `err` alone does not tell.
+    #[must_use]
+    pub fn should_retry(&self, err: &Error) -> bool {
+        matches!(
+            err.kind(),
+            ErrorKind::TransactionConflict | ErrorKind::LockHeld
+        )
+    }
+
+    /// Returns the delay before retrying after the given (1-based) failed
+    /// attempt.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        if self.backoff.is_zero() {
+            return Duration::ZERO;
+        }
+        let exp = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        // clamped before multiplying, as the factor overflows a `Duration`
+        // after enough attempts
+        let max_factor = self.max_backoff.as_secs_f64() / self.backoff.as_secs_f64();
+        let base = self
+            .backoff
+            .mul_f64(self.multiplier.powi(exp).min(max_factor))
+            .min(self.max_backoff);
+        base.mul_f64(1.0 - self.jitter * random_unit())
+    }
+}
+
+/// The value returned by a retried call, along with the number of attempts
+/// it took.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Retried<T> {
+    /// The value returned by the successful attempt.
+    pub value: T,
+    /// The number of attempts made, including the successful one.
+    pub attempts: u32,
+}
+
+/// Returns a pseudo-random number in `[0.0, 1.0]`.
+fn random_unit() -> f64 {
+    let bits = RandomState::new().hash_one(Instant::now()) >> 32;
+    f64::from(u32::try_from(bits).unwrap_or(u32::MAX)) / f64::from(u32::MAX)
+}
@@ -0,0 +1,351 @@
+use duckdb::{Connection, Row, types::Value};
+
+/// A database attached to the connection, as reported by
+/// `duckdb_databases()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DatabaseInfo {
+    /// The name of the database.
+    pub name: String,
+    /// The path of the database file, if any.
+    pub path: Option<String>,
+    /// The type of the database, e.g. `duckdb`.
+    pub kind: String,
+    /// Whether the database is attached read-only.
+    pub readonly: bool,
+    /// Whether the database is built in, e.g. `system`.
+    pub internal: bool,
+}
+
+/// A schema, as reported by `duckdb_schemas()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SchemaInfo {
+    /// The database containing the schema.
+    pub database: String,
+    /// The name of the schema.
+    pub name: String,
+    /// Whether the schema is built in.
+    pub internal: bool,
+}
+
+/// A table, as reported by `duckdb_tables()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TableInfo {
+    /// The database containing the table.
+    pub database: String,
+    /// The schema containing the table.
+    pub schema: String,
+    /// The name of the table.
+    pub name: String,
+    /// Whether the table is temporary.
+    pub temporary: bool,
+    /// Whether the table has a primary key.
+    pub has_primary_key: bool,
+    /// The estimated number of rows.
+    pub estimated_size: Option<i64>,
+    /// The number of columns.
+    pub column_count: i64,
+    /// The `CREATE TABLE` statement of the table.
+    pub sql: String,
+}
+
+/// A view, as reported by `duckdb_views()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ViewInfo {
+    /// The database containing the view.
+    pub database: String,
+    /// The schema containing the view.
+    pub schema: String,
+    /// The name of the view.
+    pub name: String,
+    /// Whether the view is temporary.
+    pub temporary: bool,
+    /// The number of columns.
+    pub column_count: i64,
+    /// The `CREATE VIEW` statement of the view.
+    pub sql: String,
+}
+
+/// A column of a table or view, as reported by `duckdb_columns()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ColumnInfo {
+    /// The database containing the table.
+    pub database: String,
+    /// The schema containing the table.
+    pub schema: String,
+    /// The table or view the column belongs to.
+    pub table: String,
+    /// The name of the column.
+    pub name: String,
+    /// The position of the column in the table, starting at 1.
+    pub index: i64,
+    /// The SQL type of the column, e.g. `VARCHAR` or `DECIMAL(18,3)`.
+    pub data_type: String,
+    /// Whether the column can contain `NULL`.
+    pub nullable: bool,
+    /// The default value expression, if any.
+    pub default: Option<String>,
+}
+
+/// An index, as reported by `duckdb_indexes()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IndexInfo {
+    /// The database containing the index.
+    pub database: String,
+    /// The schema containing the index.
+    pub schema: String,
+    /// The name of the index.
+    pub name: String,
+    /// The table the index belongs to.
+    pub table: String,
+    /// Whether the index enforces uniqueness.
+    pub unique: bool,
+    /// The `CREATE INDEX` statement of the index.
+    pub sql: Option<String>,
+}
+
+/// A constraint of a table, as reported by `duckdb_constraints()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConstraintInfo {
+    /// The database containing the table.
+    pub database: String,
+    /// The schema containing the table.
+    pub schema: String,
+    /// The table the constraint belongs to.
+    pub table: String,
+    /// The name of the constraint.
+    pub name: String,
+    /// The type of the constraint, e.g. `PRIMARY KEY` or `CHECK`.
+    pub kind: String,
+    /// The definition of the constraint.
+    pub text: String,
+    /// The constrained columns.
+    pub columns: Vec<String>,
+    /// The table referenced by a foreign key.
+    pub referenced_table: Option<String>,
+    /// The columns referenced by a foreign key.
+    pub referenced_columns: Vec<String>,
+}
+
+/// A function or macro, as reported by `duckdb_functions()`.
+///
+/// Overloaded functions are reported once per overload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FunctionInfo {
+    /// The database containing the function.
+    pub database: String,
+    /// The schema containing the function.
+    pub schema: String,
+    /// The name of the function.
+    pub name: String,
+    /// The type of the function, e.g. `scalar`, `aggregate` or `table`.
+    pub kind: String,
+    /// The names of the parameters.
+    pub parameters: Vec<String>,
+    /// The types of the parameters.
+    pub parameter_types: Vec<String>,
+    /// The return type, for scalar and aggregate functions.
+    pub return_type: Option<String>,
+    /// A description of the function.
+    pub description: Option<String>,
+    /// Whether the function is built in.
+    pub internal: bool,
+}
+
+pub(crate) fn databases(conn: &Connection) -> duckdb::Result<Vec<DatabaseInfo>> {
+    query(
+        conn,
+        "SELECT database_name, path, type, readonly, internal FROM duckdb_databases()
+         ORDER BY database_name",
+        [],
+        |row| {
+            Ok(DatabaseInfo {
+                name: row.get(0)?,
+                path: row.get(1)?,
+                kind: row.get(2)?,
+                readonly: row.get(3)?,
+                internal: row.get(4)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn schemas(conn: &Connection) -> duckdb::Result<Vec<SchemaInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, internal FROM duckdb_schemas()
+         ORDER BY database_name, schema_name",
+        [],
+        |row| {
+            Ok(SchemaInfo {
+                database: row.get(0)?,
+                name: row.get(1)?,
+                internal: row.get(2)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn tables(conn: &Connection) -> duckdb::Result<Vec<TableInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, table_name, temporary, has_primary_key,
+                estimated_size, column_count, sql
+         FROM duckdb_tables() ORDER BY database_name, schema_name, table_name",
+        [],
+        |row| {
+            Ok(TableInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                name: row.get(2)?,
+                temporary: row.get(3)?,
+                has_primary_key: row.get(4)?,
+                estimated_size: row.get(5)?,
+                column_count: row.get(6)?,
+                sql: row.get(7)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn views(conn: &Connection) -> duckdb::Result<Vec<ViewInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, view_name, temporary, column_count, sql
+         FROM duckdb_views() WHERE NOT internal
+         ORDER BY database_name, schema_name, view_name",
+        [],
+        |row| {
+            Ok(ViewInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                name: row.get(2)?,
+                temporary: row.get(3)?,
+                column_count: row.get(4)?,
+                sql: row.get(5)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn columns(conn: &Connection, table: &str) -> duckdb::Result<Vec<ColumnInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, table_name, column_name, column_index, data_type,
+                is_nullable, column_default
+         FROM duckdb_columns() WHERE table_name = ?
+         ORDER BY database_name, schema_name, column_index",
+        [table],
+        |row| {
+            Ok(ColumnInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                table: row.get(2)?,
+                name: row.get(3)?,
+                index: row.get(4)?,
+                data_type: row.get(5)?,
+                nullable: row.get(6)?,
+                default: row.get(7)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn indexes(conn: &Connection) -> duckdb::Result<Vec<IndexInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, index_name, table_name, is_unique, sql
+         FROM duckdb_indexes() ORDER BY database_name, schema_name, index_name",
+        [],
+        |row| {
+            Ok(IndexInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                name: row.get(2)?,
+                table: row.get(3)?,
+                unique: row.get(4)?,
+                sql: row.get(5)?,
+            })
+        },
+    )
+}
+
+pub(crate) fn constraints(conn: &Connection, table: &str) -> duckdb::Result<Vec<ConstraintInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, table_name, constraint_name, constraint_type,
+                constraint_text, constraint_column_names, referenced_table,
+                referenced_column_names
+         FROM duckdb_constraints() WHERE table_name = ?
+         ORDER BY database_name, schema_name, constraint_index",
+        [table],
+        |row| {
+            Ok(ConstraintInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                table: row.get(2)?,
+                name: row.get(3)?,
+                kind: row.get(4)?,
+                text: row.get(5)?,
+                columns: strings(row.get(6)?),
+                referenced_table: row.get(7)?,
+                referenced_columns: strings(row.get(8)?),
+            })
+        },
+    )
+}
+
+pub(crate) fn functions(conn: &Connection) -> duckdb::Result<Vec<FunctionInfo>> {
+    query(
+        conn,
+        "SELECT database_name, schema_name, function_name, function_type, parameters,
+                parameter_types, return_type, description, internal
+         FROM duckdb_functions() ORDER BY database_name, schema_name, function_name",
+        [],
+        |row| {
+            Ok(FunctionInfo {
+                database: row.get(0)?,
+                schema: row.get(1)?,
+                name: row.get(2)?,
+                kind: row.get(3)?,
+                parameters: strings(row.get(4)?),
+                parameter_types: strings(row.get(5)?),
+                return_type: row.get(6)?,
+                description: row.get(7)?,
+                internal: row.get(8)?,
+            })
+        },
+    )
+}
+
+fn query<T, P: duckdb::Params>(
+    conn: &Connection,
+    sql: &str,
+    params: P,
+    func: impl FnMut(&Row<'_>) -> duckdb::Result<T>,
+) -> duckdb::Result<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, func)?;
+    rows.collect()
+}
+
+/// Returns the strings of a `VARCHAR[]` value, skipping `NULL`s.
+fn strings(value: Value) -> Vec<String> {
+    let Value::List(values) = value else {
+        return Vec::new();
+    };
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::Text(text) => Some(text),
+            _ => None,
+        })
+        .collect()
+}
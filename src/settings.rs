@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use duckdb::{Connection, OptionalExt};
+
+use crate::{
+    Error,
+    sql::{quote_ident, quote_literal},
+};
+
+/// A duckdb setting, as reported by `duckdb_settings()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Setting {
+    /// The current value of the setting.
+    pub value: String,
+    /// A description of the setting.
+    pub description: String,
+    /// The type of the setting, e.g. `VARCHAR` or `BIGINT`.
+    pub input_type: String,
+    /// Whether the setting is `GLOBAL` or `LOCAL`.
+    pub scope: String,
+}
+
+/// Returns the statement used to apply a setting.
+pub(crate) fn set_sql(name: &str, value: &str) -> String {
+    format!("SET {} = {}", quote_ident(name), quote_literal(value))
+}
+
+/// Applies a setting and returns its value as read back from
+/// `duckdb_settings()`, or `None` if it is not listed there.
+///
+/// Like `SET`, the name is matched case insensitively, and may be an alias.
+pub(crate) fn set(conn: &Connection, name: &str, value: &str) -> duckdb::Result<Option<String>> {
+    conn.execute_batch(&set_sql(name, value))?;
+    conn.query_row(
+        "SELECT coalesce(value, '') FROM duckdb_settings()
+        WHERE lower(name) = lower($1)
+            OR list_contains(list_transform(aliases, a -> lower(a)), lower($1))
+        LIMIT 1",
+        [name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Checks a value read back by [`set`] against the expected one.
+///
+/// duckdb may change the case of values, so the comparison is case
+/// insensitive.
+pub(crate) fn verify(name: &str, exp: &str, got: Option<String>) -> Result<(), Error> {
+    match got {
+        Some(got) if got.trim().eq_ignore_ascii_case(exp.trim()) => Ok(()),
+        got => Err(Error::PragmaUpdate {
+            name: name.to_owned(),
+            exp: exp.to_owned(),
+            got,
+        }),
+    }
+}
+
+/// Reads all settings from `duckdb_settings()`.
+pub(crate) fn all(conn: &Connection) -> duckdb::Result<HashMap<String, Setting>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, coalesce(value, ''), description, input_type, scope FROM duckdb_settings()",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            Setting {
+                value: row.get(1)?,
+                description: row.get(2)?,
+                input_type: row.get(3)?,
+                scope: row.get(4)?,
+            },
+        ))
+    })?;
+    rows.collect()
+}
@@ -0,0 +1,18 @@
+/// Quotes `ident` as a SQL identifier, e.g. a table or setting name.
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Quotes a possibly schema qualified name, e.g. `schema.table`, quoting
+/// each of its dot separated parts as an identifier.
+pub(crate) fn quote_qualified(name: &str) -> String {
+    name.split('.')
+        .map(quote_ident)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Quotes `value` as a SQL string literal, e.g. a path or setting value.
+pub(crate) fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString, c_char},
+    fmt, ptr,
+};
+
+use duckdb::{Connection, Params, Row, arrow::record_batch::RecordBatch, ffi};
+
+use crate::{Client, Error, client::timed, handle};
+
+/// A result column of an [`AsyncStatement`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StatementColumn {
+    /// The name of the column.
+    pub name: String,
+    /// The SQL type of the column, e.g. `VARCHAR` or `DECIMAL(18,3)`.
+    pub data_type: String,
+}
+
+/// A statement prepared on the worker thread of a [`Client`], which can be
+/// run many times with different parameters.
+///
+/// Created with [`Client::prepare`]. The statement runs on the connection of
+/// the client, which caches it prepared; a statement evicted from the cache,
+/// see [`Connection::set_prepared_statement_cache_capacity`], is prepared
+/// again on its next run. Dropping the `AsyncStatement` releases it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::Client;
+/// # async fn run(client: Client) -> Result<(), async_duckdb::Error> {
+/// let insert = client.prepare("INSERT INTO events VALUES (?, ?)").await?;
+/// assert_eq!(insert.parameter_count(), 2);
+/// for (id, kind) in [(1, "click"), (2, "view")] {
+///     insert.execute((id, kind)).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncStatement {
+    client: Client,
+    id: u64,
+    sql: String,
+    parameter_count: usize,
+    columns: Vec<StatementColumn>,
+    closed: bool,
+}
+
+impl fmt::Debug for AsyncStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncStatement")
+            .field("id", &self.id)
+            .field("sql", &self.sql)
+            .field("parameter_count", &self.parameter_count)
+            .field("columns", &self.columns)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncStatement {
+    pub(crate) fn new(client: Client, sql: String, prepared: Prepared) -> Self {
+        Self {
+            client,
+            id: prepared.id,
+            sql,
+            parameter_count: prepared.parameter_count,
+            columns: prepared.columns,
+            closed: false,
+        }
+    }
+
+    /// Returns the SQL text of the statement.
+    #[must_use]
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the number of parameters of the statement.
+    #[must_use]
+    pub fn parameter_count(&self) -> usize {
+        self.parameter_count
+    }
+
+    /// Returns the result columns of the statement.
+    ///
+    /// Statements that are not queries, e.g. `INSERT` or `SET`, have none.
+    #[must_use]
+    pub fn columns(&self) -> &[StatementColumn] {
+        &self.columns
+    }
+
+    /// Runs the statement with `params`, returning the number of rows
+    /// changed.
+    pub async fn execute<P>(&self, params: P) -> Result<usize, Error>
+    where
+        P: Params + Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client
+            .session(move |conn, session| {
+                let sql = session.statements.get(id)?;
+                timed(client_id, sql, || conn.prepare_cached(sql)?.execute(params))
+            })
+            .await
+    }
+
+    /// Runs the statement with `params`, mapping every row with `func`.
+    pub async fn query_map<P, F, T>(&self, params: P, func: F) -> Result<Vec<T>, Error>
+    where
+        P: Params + Send + 'static,
+        F: FnMut(&Row<'_>) -> duckdb::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client
+            .session(move |conn, session| {
+                let sql = session.statements.get(id)?;
+                timed(client_id, sql, || {
+                    conn.prepare_cached(sql)?.query_map(params, func)?.collect()
+                })
+            })
+            .await
+    }
+
+    /// Runs the statement with `params`, returning the result as arrow
+    /// record batches.
+    pub async fn query_arrow<P>(&self, params: P) -> Result<Vec<RecordBatch>, Error>
+    where
+        P: Params + Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client
+            .session(move |conn, session| {
+                let sql = session.statements.get(id)?;
+                timed(client_id, sql, || {
+                    Ok(conn.prepare_cached(sql)?.query_arrow(params)?.collect())
+                })
+            })
+            .await
+    }
+
+    /// Runs the statement with `params`, blocking the current thread.
+    ///
+    /// See [`AsyncStatement::execute`].
+    pub fn execute_blocking<P>(&self, params: P) -> Result<usize, Error>
+    where
+        P: Params + Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client.session_blocking(move |conn, session| {
+            let sql = session.statements.get(id)?;
+            timed(client_id, sql, || conn.prepare_cached(sql)?.execute(params))
+        })
+    }
+
+    /// Runs the statement with `params`, mapping every row with `func`,
+    /// blocking the current thread.
+    ///
+    /// See [`AsyncStatement::query_map`].
+    pub fn query_map_blocking<P, F, T>(&self, params: P, func: F) -> Result<Vec<T>, Error>
+    where
+        P: Params + Send + 'static,
+        F: FnMut(&Row<'_>) -> duckdb::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client.session_blocking(move |conn, session| {
+            let sql = session.statements.get(id)?;
+            timed(client_id, sql, || {
+                conn.prepare_cached(sql)?.query_map(params, func)?.collect()
+            })
+        })
+    }
+
+    /// Runs the statement with `params`, returning the result as arrow
+    /// record batches, blocking the current thread.
+    ///
+    /// See [`AsyncStatement::query_arrow`].
+    pub fn query_arrow_blocking<P>(&self, params: P) -> Result<Vec<RecordBatch>, Error>
+    where
+        P: Params + Send + 'static,
+    {
+        let (id, client_id) = (self.id, self.client.id());
+        self.client.session_blocking(move |conn, session| {
+            let sql = session.statements.get(id)?;
+            timed(client_id, sql, || {
+                Ok(conn.prepare_cached(sql)?.query_arrow(params)?.collect())
+            })
+        })
+    }
+
+    /// Releases the prepared statement on the worker thread.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.closed = true;
+        let id = self.id;
+        self.client
+            .session(move |_, session| {
+                session.statements.close(id);
+                Ok(())
+            })
+            .await
+    }
+
+    /// Releases the prepared statement, blocking the current thread.
+    ///
+    /// See [`AsyncStatement::close`].
+    pub fn close_blocking(mut self) -> Result<(), Error> {
+        self.closed = true;
+        let id = self.id;
+        self.client.session_blocking(move |_, session| {
+            session.statements.close(id);
+            Ok(())
+        })
+    }
+}
+
+impl Drop for AsyncStatement {
+    fn drop(&mut self) {
+        if !self.closed {
+            let id = self.id;
+            self.client
+                .session_detached(move |_, session| session.statements.close(id));
+        }
+    }
+}
+
+/// A statement registered with [`Statements::prepare`].
+pub(crate) struct Prepared {
+    id: u64,
+    parameter_count: usize,
+    columns: Vec<StatementColumn>,
+}
+
+/// The prepared statements of a worker thread, keyed by id.
+///
+/// Only the SQL text is kept: the statements are prepared through the
+/// statement cache of the connection whenever they run.
+#[derive(Default)]
+pub(crate) struct Statements {
+    next_id: u64,
+    open: HashMap<u64, String>,
+}
+
+impl Statements {
+    /// Prepares `sql` and registers it.
+    pub(crate) fn prepare(&mut self, conn: &Connection, sql: &str) -> duckdb::Result<Prepared> {
+        let parameter_count = conn.prepare_cached(sql)?.parameter_count();
+        let columns = describe(conn, sql)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.insert(id, sql.to_owned());
+        Ok(Prepared {
+            id,
+            parameter_count,
+            columns,
+        })
+    }
+
+    /// Returns the SQL text of statement `id`.
+    fn get(&self, id: u64) -> Result<&str, Error> {
+        self.open.get(&id).map(String::as_str).ok_or(Error::Closed)
+    }
+
+    /// Releases statement `id`.
+    pub(crate) fn close(&mut self, id: u64) {
+        self.open.remove(&id);
+    }
+}
+
+/// Describes the result columns of `sql`.
+///
+/// duckdb-rs only describes the columns of executed statements, so `sql` is
+/// prepared once more through the C API.
+fn describe(conn: &Connection, sql: &str) -> duckdb::Result<Vec<StatementColumn>> {
+    let sql = CString::new(sql)?;
+    let columns = handle::with_raw(&conn.interrupt_handle(), |conn| {
+        let mut stmt = ptr::null_mut();
+        // SAFETY: the connection is open, and `stmt` is destroyed whether or
+        // not preparing succeeds, as duckdb requires.
+        unsafe {
+            let columns = match ffi::duckdb_prepare(conn, sql.as_ptr(), &raw mut stmt) {
+                ffi::DuckDBSuccess => columns(stmt),
+                _ => Vec::new(),
+            };
+            ffi::duckdb_destroy_prepare(&raw mut stmt);
+            columns
+        }
+    });
+    Ok(columns.unwrap_or_default())
+}
+
+/// Returns the result columns of `stmt`, or none if it is not a query.
+///
+/// # Safety
+///
+/// `stmt` must be a prepared statement.
+unsafe fn columns(stmt: ffi::duckdb_prepared_statement) -> Vec<StatementColumn> {
+    // SAFETY: guaranteed by the caller, the names and types are freed once
+    // read.
+    unsafe {
+        if !matches!(
+            ffi::duckdb_prepared_statement_type(stmt),
+            ffi::duckdb_statement_type_DUCKDB_STATEMENT_TYPE_SELECT
+                | ffi::duckdb_statement_type_DUCKDB_STATEMENT_TYPE_EXPLAIN
+                | ffi::duckdb_statement_type_DUCKDB_STATEMENT_TYPE_CALL
+        ) {
+            return Vec::new();
+        }
+        (0..ffi::duckdb_prepared_statement_column_count(stmt))
+            .map(|idx| StatementColumn {
+                name: take_string(ffi::duckdb_prepared_statement_column_name(stmt, idx).cast_mut()),
+                data_type: type_name(ffi::duckdb_prepared_statement_column_logical_type(
+                    stmt, idx,
+                )),
+            })
+            .collect()
+    }
+}
+
+/// Returns the SQL name of `ty` as `DESCRIBE` shows it, e.g.
+/// `DECIMAL(18,3)` or `INTEGER[]`, and destroys `ty`.
+///
+/// # Safety
+///
+/// `ty` must be a logical type that is not used afterwards.
+unsafe fn type_name(mut ty: ffi::duckdb_logical_type) -> String {
+    // SAFETY: guaranteed by the caller, the child types and strings returned
+    // by duckdb are freed once read.
+    unsafe {
+        let alias = ffi::duckdb_logical_type_get_alias(ty);
+        let name = if alias.is_null() {
+            named_type(ty)
+        } else {
+            take_string(alias)
+        };
+        ffi::duckdb_destroy_logical_type(&raw mut ty);
+        name
+    }
+}
+
+/// Returns the SQL name of `ty`, ignoring its alias.
+///
+/// # Safety
+///
+/// `ty` must be a logical type.
+unsafe fn named_type(ty: ffi::duckdb_logical_type) -> String {
+    let members =
+        |count, name: unsafe extern "C" fn(_, _) -> _, member: unsafe extern "C" fn(_, _) -> _| {
+            (0..count)
+                // SAFETY: `idx` is within the members of `ty`.
+                .map(|idx| unsafe {
+                    format!(
+                        "{} {}",
+                        quoted(&take_string(name(ty, idx))),
+                        type_name(member(ty, idx))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+    // SAFETY: guaranteed by the caller, each accessor matches the type id.
+    unsafe {
+        let name = match ffi::duckdb_get_type_id(ty) {
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN => "BOOLEAN",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TINYINT => "TINYINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_SMALLINT => "SMALLINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_INTEGER => "INTEGER",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_BIGINT => "BIGINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_HUGEINT => "HUGEINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UTINYINT => "UTINYINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_USMALLINT => "USMALLINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UINTEGER => "UINTEGER",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UBIGINT => "UBIGINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UHUGEINT => "UHUGEINT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_FLOAT => "FLOAT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_DOUBLE => "DOUBLE",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP => "TIMESTAMP",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_S => "TIMESTAMP_S",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_MS => "TIMESTAMP_MS",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_NS => "TIMESTAMP_NS",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIMESTAMP_TZ => "TIMESTAMP WITH TIME ZONE",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_DATE => "DATE",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIME => "TIME",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIME_NS => "TIME_NS",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_TIME_TZ => "TIME WITH TIME ZONE",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_INTERVAL => "INTERVAL",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR => "VARCHAR",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_BLOB => "BLOB",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_BIT => "BIT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_BIGNUM => "BIGNUM",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UUID => "UUID",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_GEOMETRY => "GEOMETRY",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_VARIANT => "VARIANT",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_SQLNULL => "\"NULL\"",
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_DECIMAL => {
+                return format!(
+                    "DECIMAL({},{})",
+                    ffi::duckdb_decimal_width(ty),
+                    ffi::duckdb_decimal_scale(ty)
+                );
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_ENUM => {
+                let values: Vec<_> = (0..ffi::duckdb_enum_dictionary_size(ty))
+                    .map(|idx| {
+                        let value = take_string(ffi::duckdb_enum_dictionary_value(ty, idx.into()));
+                        format!("'{}'", value.replace('\'', "''"))
+                    })
+                    .collect();
+                return format!("ENUM({})", values.join(", "));
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_LIST => {
+                return format!("{}[]", type_name(ffi::duckdb_list_type_child_type(ty)));
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_ARRAY => {
+                return format!(
+                    "{}[{}]",
+                    type_name(ffi::duckdb_array_type_child_type(ty)),
+                    ffi::duckdb_array_type_array_size(ty)
+                );
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_MAP => {
+                return format!(
+                    "MAP({}, {})",
+                    type_name(ffi::duckdb_map_type_key_type(ty)),
+                    type_name(ffi::duckdb_map_type_value_type(ty))
+                );
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_STRUCT => {
+                let fields = members(
+                    ffi::duckdb_struct_type_child_count(ty),
+                    ffi::duckdb_struct_type_child_name,
+                    ffi::duckdb_struct_type_child_type,
+                );
+                return format!("STRUCT({fields})");
+            }
+            ffi::DUCKDB_TYPE_DUCKDB_TYPE_UNION => {
+                let fields = members(
+                    ffi::duckdb_union_type_member_count(ty),
+                    ffi::duckdb_union_type_member_name,
+                    ffi::duckdb_union_type_member_type,
+                );
+                return format!("UNION({fields})");
+            }
+            _ => "UNKNOWN",
+        };
+        name.to_owned()
+    }
+}
+
+/// Quotes `name` unless it is a plain lowercase identifier, like `DESCRIBE`
+/// shows the fields of structs.
+fn quoted(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain {
+        name.to_owned()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Copies a string allocated by duckdb and frees it.
+///
+/// # Safety
+///
+/// `ptr` must be a null-terminated string allocated by duckdb, or null.
+unsafe fn take_string(ptr: *mut c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        let value = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+        ffi::duckdb_free(ptr.cast());
+        value
+    }
+}
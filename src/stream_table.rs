@@ -0,0 +1,184 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use crossbeam_channel::bounded;
+use duckdb::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    vtab::{
+        BindInfo, InitInfo, TableFunctionInfo, VTab, record_batch_to_duckdb_data_chunk,
+        to_duckdb_logical_type,
+    },
+};
+use futures_channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use futures_util::{Stream, StreamExt};
+
+use crate::registry::Init;
+
+/// Returns the maximum number of rows duckdb accepts in a single data chunk.
+pub(crate) fn vector_size() -> usize {
+    // SAFETY: `duckdb_vector_size` only returns a constant.
+    let size = unsafe { duckdb::ffi::duckdb_vector_size() };
+    usize::try_from(size).unwrap_or(usize::MAX)
+}
+
+/// A request from the worker for the next batch of a scan.
+type Pull = crossbeam_channel::Sender<Option<RecordBatch>>;
+
+/// A scan of a stream table, started by the worker.
+struct Scan {
+    pulls: UnboundedReceiver<Pull>,
+}
+
+/// Shared between the table function registrations and the driver.
+#[derive(Clone)]
+struct Source {
+    schema: SchemaRef,
+    scans: UnboundedSender<Scan>,
+}
+
+/// The driver of a table function registered with
+/// [`Client::register_stream_table`](crate::Client::register_stream_table).
+///
+/// The driver creates a stream for every scan of the table and forwards its
+/// batches to the worker thread. It must be spawned (or otherwise polled) on
+/// the caller's runtime; queries that scan the table block until it is. The
+/// driver completes once the client or pool it was registered with has been
+/// closed and dropped.
+///
+/// A scan run by a `*_blocking` call on a thread that also polls the driver,
+/// e.g. on a single threaded runtime, never completes: the worker waits for a
+/// batch that the blocked thread cannot produce.
+#[must_use = "stream tables produce no rows unless the driver is polled"]
+pub struct StreamTableDriver {
+    fut: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl Future for StreamTableDriver {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+/// Returns the [`Init`] that registers the stream table as `name`, and the
+/// driver serving its scans.
+pub(crate) fn stream_table<F, S>(
+    name: &str,
+    schema: SchemaRef,
+    factory: F,
+) -> (Init, StreamTableDriver)
+where
+    F: Fn() -> S + Send + 'static,
+    S: Stream<Item = RecordBatch> + Send + 'static,
+{
+    let (scans_tx, scans_rx) = unbounded::<Scan>();
+    let fut = scans_rx.for_each_concurrent(None, move |scan| serve(scan, factory()));
+    let source = Source {
+        schema,
+        scans: scans_tx,
+    };
+    let name = name.to_owned();
+    let init: Init = Arc::new(move |conn| {
+        conn.register_table_function_with_extra_info::<StreamVTab, _>(&name, &source)
+    });
+    let driver = StreamTableDriver { fut: Box::pin(fut) };
+    (init, driver)
+}
+
+/// Answers the pulls of a scan with batches from `stream`.
+async fn serve<S: Stream<Item = RecordBatch>>(mut scan: Scan, stream: S) {
+    let mut stream = std::pin::pin!(stream);
+    while let Some(pull) = scan.pulls.next().await {
+        let batch = stream.next().await;
+        let done = batch.is_none();
+        _ = pull.send(batch);
+        if done {
+            break;
+        }
+    }
+}
+
+struct StreamVTab;
+
+struct StreamInit {
+    pulls: UnboundedSender<Pull>,
+    state: Mutex<ScanState>,
+}
+
+#[derive(Default)]
+struct ScanState {
+    batch: Option<RecordBatch>,
+    offset: usize,
+    done: bool,
+}
+
+impl VTab for StreamVTab {
+    type BindData = ();
+    type InitData = StreamInit;
+
+    fn bind(bind: &BindInfo) -> Result<(), Box<dyn std::error::Error>> {
+        // SAFETY: the extra info is set to a `Source` at registration.
+        let source = unsafe { &*bind.get_extra_info::<Source>() };
+        for field in source.schema.fields() {
+            bind.add_result_column(field.name(), to_duckdb_logical_type(field.data_type())?);
+        }
+        Ok(())
+    }
+
+    fn init(init: &InitInfo) -> Result<StreamInit, Box<dyn std::error::Error>> {
+        // SAFETY: the extra info is set to a `Source` at registration.
+        let source = unsafe { &*init.get_extra_info::<Source>() };
+        init.set_max_threads(1);
+        let (pulls_tx, pulls_rx) = unbounded();
+        source
+            .scans
+            .unbounded_send(Scan { pulls: pulls_rx })
+            .map_err(|_| "stream table driver was dropped")?;
+        Ok(StreamInit {
+            pulls: pulls_tx,
+            state: Mutex::default(),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut duckdb::core::DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init = func.get_init_data();
+        let mut state = init.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if state.done {
+                output.set_len(0);
+                return Ok(());
+            }
+            if let Some(batch) = &state.batch
+                && state.offset < batch.num_rows()
+            {
+                if batch.num_columns() != output.num_columns() {
+                    return Err("stream table batch does not match the table schema".into());
+                }
+                let len = vector_size().min(batch.num_rows() - state.offset);
+                record_batch_to_duckdb_data_chunk(&batch.slice(state.offset, len), output)?;
+                state.offset += len;
+                return Ok(());
+            }
+            let (tx, rx) = bounded(1);
+            init.pulls
+                .unbounded_send(tx)
+                .map_err(|_| "stream table driver was dropped")?;
+            match rx.recv() {
+                Ok(Some(batch)) => {
+                    state.batch = Some(batch);
+                    state.offset = 0;
+                }
+                Ok(None) => state.done = true,
+                Err(_) => return Err("stream table driver was dropped".into()),
+            }
+        }
+    }
+}
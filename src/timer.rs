@@ -0,0 +1,96 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, unbounded};
+use futures_channel::oneshot;
+
+use crate::Error;
+
+/// A pending [`sleep`], woken at its deadline.
+struct Timer {
+    deadline: Instant,
+    tx: oneshot::Sender<()>,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    // reversed, so the heap pops the earliest deadline first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+static TIMERS: OnceLock<Sender<Timer>> = OnceLock::new();
+
+/// Runtime agnostic async sleep.
+///
+/// All sleeps are served by a single timer thread, started on first use.
+pub(crate) async fn sleep(dur: Duration) -> Result<(), Error> {
+    if dur.is_zero() {
+        return Ok(());
+    }
+    let (tx, rx) = oneshot::channel();
+    let timer = Timer {
+        deadline: Instant::now() + dur,
+        tx,
+    };
+    if timers()?.send(timer).is_ok() {
+        _ = rx.await;
+    }
+    Ok(())
+}
+
+/// Returns the channel of the timer thread, starting it on first use.
+fn timers() -> Result<&'static Sender<Timer>, Error> {
+    if let Some(timers) = TIMERS.get() {
+        return Ok(timers);
+    }
+    let (tx, rx) = unbounded();
+    thread::Builder::new()
+        .name("async-duckdb-timer".to_owned())
+        .spawn(move || run(&rx))?;
+    // a thread started concurrently exits once its channel is dropped
+    Ok(TIMERS.get_or_init(|| tx))
+}
+
+fn run(rx: &Receiver<Timer>) {
+    let mut timers = BinaryHeap::new();
+    loop {
+        let now = Instant::now();
+        while timers
+            .peek()
+            .is_some_and(|timer: &Timer| timer.deadline <= now)
+        {
+            if let Some(timer) = timers.pop() {
+                _ = timer.tx.send(());
+            }
+        }
+        let next = match timers.peek() {
+            Some(timer) => rx.recv_deadline(timer.deadline),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(timer) => timers.push(timer),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
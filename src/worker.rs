@@ -0,0 +1,174 @@
+use std::{
+    fmt, io,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+type Hook = Arc<dyn Fn() + Send + Sync>;
+
+/// Options for the worker thread of a [`Client`](crate::Client), or the
+/// worker threads of a [`Pool`](crate::Pool).
+///
+/// # Examples
+///
+/// ```rust
+/// # use async_duckdb::{PoolBuilder, ThreadBuilder};
+/// # async fn run() -> Result<(), async_duckdb::Error> {
+/// let threads = ThreadBuilder::new()
+///     .name("duckdb")
+///     .stack_size(8 * 1024 * 1024)
+///     .on_thread_start(|| println!("worker started"));
+/// // the workers are named duckdb-0, duckdb-1, ...
+/// let pool = PoolBuilder::new().thread_builder(threads).open().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ThreadBuilder {
+    name: Option<String>,
+    stack_size: Option<usize>,
+    #[cfg(target_os = "linux")]
+    affinity: Option<Vec<usize>>,
+    on_start: Option<Hook>,
+    on_stop: Option<Hook>,
+}
+
+impl ThreadBuilder {
+    /// Returns a new [`ThreadBuilder`] with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the name of the worker thread.
+    ///
+    /// The workers of a [`Pool`](crate::Pool) are named `<name>-<index>`.
+    /// By default, worker threads are unnamed.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Specify the stack size of the worker thread, in bytes.
+    #[must_use]
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Pin the worker thread to the given CPUs.
+    ///
+    /// Opening the client fails if the affinity cannot be set.
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn affinity<I: IntoIterator<Item = usize>>(mut self, cpus: I) -> Self {
+        self.affinity = Some(cpus.into_iter().collect());
+        self
+    }
+
+    /// Specify a function to run on the worker thread when it starts, before
+    /// the connection is opened.
+    ///
+    /// If it panics, opening fails with an [`Error::Io`](crate::Error::Io)
+    /// error and the stop hook is not run.
+    #[must_use]
+    pub fn on_thread_start<F: Fn() + Send + Sync + 'static>(mut self, func: F) -> Self {
+        self.on_start = Some(Arc::new(func));
+        self
+    }
+
+    /// Specify a function to run on the worker thread when it stops, after
+    /// the connection is closed.
+    #[must_use]
+    pub fn on_thread_stop<F: Fn() + Send + Sync + 'static>(mut self, func: F) -> Self {
+        self.on_stop = Some(Arc::new(func));
+        self
+    }
+
+    /// Spawns a worker thread running `func`, passing it whether the thread
+    /// could be set up.
+    pub(crate) fn spawn<F>(&self, index: Option<usize>, func: F) -> io::Result<JoinHandle<()>>
+    where
+        F: FnOnce(io::Result<()>) + Send + 'static,
+    {
+        let mut builder = thread::Builder::new();
+        if let Some(name) = &self.name {
+            builder = builder.name(match index {
+                Some(index) => format!("{name}-{index}"),
+                None => name.clone(),
+            });
+        }
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        #[cfg(target_os = "linux")]
+        let affinity = self.affinity.clone();
+        let (on_start, on_stop) = (self.on_start.clone(), self.on_stop.clone());
+        builder.spawn(move || {
+            #[cfg(target_os = "linux")]
+            let res = affinity.map_or(Ok(()), |cpus| set_affinity(&cpus));
+            #[cfg(not(target_os = "linux"))]
+            let res = Ok(());
+            let started = on_start.map_or(Ok(()), |on_start| {
+                panic::catch_unwind(AssertUnwindSafe(|| on_start()))
+                    .map_err(|_| io::Error::other("the thread start hook panicked"))
+            });
+            // runs the hook even if the worker panics, once the thread has
+            // started
+            let _stop = started.is_ok().then(|| OnStop(on_stop));
+            func(res.and(started));
+        })
+    }
+}
+
+impl fmt::Debug for ThreadBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ThreadBuilder");
+        debug
+            .field("name", &self.name)
+            .field("stack_size", &self.stack_size);
+        #[cfg(target_os = "linux")]
+        debug.field("affinity", &self.affinity);
+        debug
+            .field("on_thread_start", &self.on_start.is_some())
+            .field("on_thread_stop", &self.on_stop.is_some())
+            .finish()
+    }
+}
+
+struct OnStop(Option<Hook>);
+
+impl Drop for OnStop {
+    fn drop(&mut self) {
+        if let Some(on_stop) = &self.0 {
+            on_stop();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+    let max = usize::try_from(libc::CPU_SETSIZE).unwrap_or_default();
+    // SAFETY: `cpu_set_t` is a plain bit set, for which all zeroes is valid.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cpu {cpu} is out of range"),
+            ));
+        }
+        // SAFETY: `cpu` is within the bounds of the set.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: `set` is a valid cpu set of the given size, and pid 0 is the
+    // calling thread.
+    let res = unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &raw const set) };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
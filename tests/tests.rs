@@ -1,9 +1,27 @@
 #![expect(clippy::unwrap_used)]
 #![expect(clippy::unnecessary_wraps)]
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering::SeqCst},
+    },
+    time::Duration,
+};
+
+use async_duckdb::{
+    ClientBuilder, ClosePolicy, CsvOptions, Error, ErrorKind, HealthCheck, HealthStatus,
+    MaintenancePolicy, Migration, Migrations, PoolBuilder, Priority, RetryPolicy, ThreadBuilder,
+};
+use futures_util::{
+    StreamExt,
+    future::{join, join_all},
+};
 
-use async_duckdb::{ClientBuilder, Error, PoolBuilder};
 #[test]
 fn test_blocking_client() {
     let tmp_dir = tempfile::tempdir().unwrap();
@@ -57,6 +75,24 @@ async_test!(test_config_fn);
 async_test!(test_concurrency);
 async_test!(test_pool);
 async_test!(test_pool_conn_for_each);
+async_test!(test_error_kinds);
+async_test!(test_lock_held);
+async_test!(test_conn_mut_retry);
+async_test!(test_conn_mut_retry_lock);
+async_test!(test_settings);
+async_test!(test_pool_set_setting_all);
+async_test!(test_export_import);
+async_test!(test_conn_with_progress);
+async_test!(test_migrations);
+async_test!(test_schema_introspection);
+async_test!(test_pool_reload);
+async_test!(test_maintenance);
+async_test!(test_database_copies);
+async_test!(test_pool_resize);
+async_test!(test_priority);
+async_test!(test_group_commit);
+async_test!(test_batch);
+async_test!(test_thread_builder);
 
 async fn test_config_fn() {
     let tmp_dir = tempfile::tempdir().unwrap();
@@ -257,3 +293,1746 @@ async fn test_pool_conn_for_each() {
     // cleanup
     pool.close().await.expect("closing client conn");
 }
+
+async fn test_error_kinds() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE testing (id INTEGER PRIMARY KEY, val TEXT NOT NULL);
+                INSERT INTO testing VALUES (1, 'value1');",
+            )
+        })
+        .await
+        .expect("writing schema and seed data");
+
+    let err = client
+        .conn(|conn| conn.execute("INSERT INTO testing VALUES (1, ?)", ["value2"]))
+        .await
+        .unwrap_err();
+    assert!(err.is_constraint_violation(), "{err}");
+    assert!(err.context().is_none());
+    assert!(matches!(err, Error::Duckdb(_)));
+
+    let err = err.with_label("seeding");
+    assert_eq!(err.context().unwrap().label.as_deref(), Some("seeding"));
+    assert!(err.to_string().ends_with("(seeding)"), "{err}");
+
+    let sql = "SELECT * FROM missing";
+    let err = client
+        .conn(move |conn| conn.execute_batch(sql))
+        .await
+        .unwrap_err()
+        .with_sql(sql);
+    assert_eq!(err.kind(), ErrorKind::Catalog, "{err}");
+    assert_eq!(err.context().unwrap().sql.as_deref(), Some(sql));
+    assert!(err.duckdb().is_some());
+
+    let err = client
+        .conn(|conn| {
+            let other = conn.try_clone()?;
+            conn.execute_batch("BEGIN; UPDATE testing SET val = 'a' WHERE id = 1;")?;
+            other.execute_batch("BEGIN; UPDATE testing SET val = 'b' WHERE id = 1;")
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_transaction_conflict(), "{err}");
+
+    client.close().await.expect("closing client conn");
+    let err = client.conn(|_| Ok(())).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Closed);
+    assert!(err.context().is_none());
+}
+
+/// The environment variable telling [`lock_holder`] which database to lock.
+const HOLD_LOCK: &str = "ASYNC_DUCKDB_HOLD_LOCK";
+
+/// Opens `path` in a child process, which holds its lock until its stdin is
+/// closed.
+fn hold_lock(path: &Path) -> Child {
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args(["lock_holder", "--exact", "--nocapture"])
+        .env(HOLD_LOCK, path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawning the lock holder");
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    // libtest prints the name of the test on the same line
+    let locked = stdout
+        .lines()
+        .any(|line| line.is_ok_and(|line| line.ends_with("locked")));
+    assert!(locked, "the lock holder exited before locking");
+    child
+}
+
+/// Releases the lock taken by [`hold_lock`].
+fn release_lock(mut child: Child) {
+    drop(child.stdin.take());
+    child.wait().unwrap();
+}
+
+#[test]
+fn lock_holder() {
+    let Some(path) = std::env::var_os(HOLD_LOCK) else {
+        return;
+    };
+    let conn = duckdb::Connection::open(path).unwrap();
+    println!("locked");
+    _ = std::io::stdin().read_to_end(&mut Vec::new());
+    drop(conn);
+}
+
+async fn test_lock_held() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("duck.db");
+    let holder = hold_lock(&path);
+    let err = ClientBuilder::new()
+        .path(&path)
+        .open()
+        .await
+        .err()
+        .expect("opened a locked database");
+    assert!(err.is_lock_held(), "{err}");
+    release_lock(holder);
+    ClientBuilder::new()
+        .path(&path)
+        .open()
+        .await
+        .expect("client unable to be opened")
+        .close()
+        .await
+        .expect("closing client conn");
+}
+
+async fn test_conn_mut_retry() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE testing (id INTEGER PRIMARY KEY, val TEXT NOT NULL);
+                INSERT INTO testing VALUES (1, 'value1');",
+            )
+        })
+        .await
+        .expect("writing schema and seed data");
+    let policy = RetryPolicy::new().backoff(Duration::from_millis(1));
+
+    // the first attempt conflicts with another transaction, the second succeeds
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls_ = calls.clone();
+    let res = client
+        .conn_mut_retry(&policy, move |conn| {
+            if calls_.fetch_add(1, SeqCst) == 0 {
+                let other = conn.try_clone()?;
+                other.execute_batch("BEGIN; UPDATE testing SET val = 'other' WHERE id = 1;")?;
+                conn.execute_batch("BEGIN; UPDATE testing SET val = 'retried' WHERE id = 1;")?;
+            }
+            conn.execute("UPDATE testing SET val = 'retried' WHERE id = 1", [])
+        })
+        .await
+        .expect("retrying conflict");
+    assert_eq!(res.attempts, 2);
+    assert_eq!(res.value, 1);
+    assert_eq!(calls.load(SeqCst), 2);
+    let val: String = client
+        .conn(|conn| conn.query_row("SELECT val FROM testing WHERE id = 1", [], |row| row.get(0)))
+        .await
+        .unwrap();
+    assert_eq!(val, "retried");
+
+    // non-transient errors are not retried
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls_ = calls.clone();
+    let err = client
+        .conn_mut_retry(&policy, move |conn| {
+            calls_.fetch_add(1, SeqCst);
+            conn.execute("INSERT INTO testing VALUES (1, 'dup')", [])
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_constraint_violation(), "{err}");
+    assert_eq!(calls.load(SeqCst), 1);
+
+    // queries interrupted by the caller are not retried
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls_ = calls.clone();
+    let err = client
+        .conn_mut_retry(&policy, move |conn| {
+            calls_.fetch_add(1, SeqCst);
+            let interrupt = conn.interrupt_handle();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                interrupt.interrupt();
+            });
+            conn.query_row("SELECT sum(i) FROM range(1000000000000) t(i)", [], |row| {
+                row.get::<_, i128>(0)
+            })
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_interrupted(), "{err}");
+    assert_eq!(calls.load(SeqCst), 1);
+
+    // queries interrupted by closing the client are retried, and then find
+    // it closed
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls_ = calls.clone();
+    let (started_tx, started_rx) = futures_channel::oneshot::channel();
+    let started_tx = Mutex::new(Some(started_tx));
+    let long = client.conn_mut_retry(&policy, move |conn| {
+        calls_.fetch_add(1, SeqCst);
+        if let Some(tx) = started_tx.lock().unwrap().take() {
+            _ = tx.send(());
+        }
+        conn.query_row("SELECT sum(i) FROM range(1000000000000) t(i)", [], |row| {
+            row.get::<_, i128>(0)
+        })
+    });
+    let close = async {
+        started_rx.await.unwrap();
+        let policy = ClosePolicy::new().grace_period(Duration::from_millis(50));
+        client.close_with(policy).await
+    };
+    let (long, closed) = join(long, close).await;
+    closed.unwrap();
+    assert_eq!(long.unwrap_err().kind(), ErrorKind::Closed);
+    assert_eq!(calls.load(SeqCst), 1);
+}
+
+async fn test_conn_mut_retry_lock() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("other.db");
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    // enough attempts to overflow the backoff without its upper bound
+    let policy = RetryPolicy::new()
+        .max_attempts(100)
+        .backoff(Duration::from_micros(1))
+        .max_backoff(Duration::from_micros(100));
+
+    // the database is locked by another process until the last attempt
+    let holder = Arc::new(Mutex::new(Some(hold_lock(&path))));
+    let calls = Arc::new(AtomicU32::new(0));
+    let calls_ = calls.clone();
+    let attach = format!("ATTACH '{}' AS other; DETACH other;", path.display());
+    let res = client
+        .conn_mut_retry(&policy, move |conn| {
+            if calls_.fetch_add(1, SeqCst) == 99 {
+                release_lock(holder.lock().unwrap().take().unwrap());
+            }
+            conn.execute_batch(&attach)
+        })
+        .await
+        .expect("retrying held lock");
+    assert_eq!(res.attempts, 100);
+    assert_eq!(calls.load(SeqCst), 100);
+
+    client.close().await.expect("closing client conn");
+}
+
+async_test!(test_pool_conn_mut_retry);
+
+async fn test_pool_conn_mut_retry() {
+    let policy = RetryPolicy::new()
+        .max_attempts(2)
+        .backoff(Duration::from_millis(1));
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .retry_policy(policy)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+
+    // each attempt runs on a different worker thread, with its own in-memory
+    // database
+    let threads = Arc::new(Mutex::new(Vec::new()));
+    let threads_ = threads.clone();
+    let err = pool
+        .conn_mut_retry(pool.retry_policy(), move |conn| {
+            threads_.lock().unwrap().push(std::thread::current().id());
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS testing (id INTEGER PRIMARY KEY, val TEXT NOT NULL);
+                INSERT OR IGNORE INTO testing VALUES (1, 'value1');",
+            )?;
+            let other = conn.try_clone()?;
+            other.execute_batch("BEGIN; UPDATE testing SET val = 'other' WHERE id = 1;")?;
+            conn.execute_batch("BEGIN; UPDATE testing SET val = 'retried' WHERE id = 1;")
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_transaction_conflict(), "{err}");
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 2);
+    assert_ne!(threads[0], threads[1]);
+}
+
+async fn test_settings() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+
+    client
+        .set_setting("default_order", "desc")
+        .await
+        .expect("setting default_order");
+    client
+        .set_setting("threads", 1)
+        .await
+        .expect("setting threads");
+    let settings = client.settings().await.expect("reading settings");
+    assert_eq!(settings["default_order"].value.to_lowercase(), "desc");
+    assert_eq!(settings["threads"].value, "1");
+
+    // duckdb normalizes memory sizes, so the value read back differs
+    let err = client.set_setting("memory_limit", "1GB").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PragmaUpdate, "{err}");
+
+    // aliases and other cases of a name resolve to the same setting
+    client
+        .set_setting("worker_threads", 2)
+        .await
+        .expect("setting worker_threads");
+    client
+        .set_setting("Default_Order", "asc")
+        .await
+        .expect("setting Default_Order");
+    let settings = client.settings().await.expect("reading settings");
+    assert_eq!(settings["threads"].value, "2");
+    assert_eq!(settings["default_order"].value.to_lowercase(), "asc");
+
+    let err = client.set_setting("not_a_setting", 1).await.unwrap_err();
+    assert!(err.context().unwrap().sql.is_some());
+}
+
+async fn test_pool_set_setting_all() {
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    for res in pool.set_setting_all("default_order", "desc").await {
+        res.expect("setting default_order");
+    }
+    let res = pool
+        .conn_for_each(|conn| {
+            conn.query_row(
+                "SELECT value FROM duckdb_settings() WHERE name = 'default_order'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+        })
+        .await;
+    for r in res {
+        assert_eq!(r.unwrap().to_lowercase(), "desc");
+    }
+    pool.close().await.expect("closing pool");
+}
+
+async fn test_export_import() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("it's.csv");
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+
+    let options = CsvOptions::new().delimiter('|').header(true).quote('\'');
+    let rows = client
+        .export(
+            "SELECT i AS id, 'row ' || i AS name FROM range(100) t(i)",
+            &path,
+            options.clone(),
+        )
+        .await
+        .expect("exporting csv");
+    assert_eq!(rows, 100);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("id|name\n"), "{contents}");
+
+    let rows = client
+        .conn(|conn| {
+            conn.execute_batch(
+                "CREATE SCHEMA staging; CREATE TABLE staging.imported (id BIGINT, name VARCHAR)",
+            )
+        })
+        .await
+        .and(client.import("staging.imported", &path, options).await)
+        .expect("importing csv");
+    assert_eq!(rows, 100);
+    let count: i64 = client
+        .conn(|conn| {
+            conn.query_row("SELECT count(*) FROM staging.imported", [], |row| {
+                row.get(0)
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(count, 100);
+}
+
+async fn test_conn_with_progress() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| conn.execute_batch("CREATE TEMP TABLE steps AS SELECT * FROM range(7) t(i)"))
+        .await
+        .expect("creating temp table");
+
+    // runs on the connection of the client, which sees its temporary tables
+    let (res, progress) = client.conn_with_progress(|conn| {
+        conn.query_row(
+            "SELECT count(*) FROM range(10000000) r(i) JOIN steps s ON r.i % 7 = s.i",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+    });
+    let (res, updates) = join(res, progress.collect::<Vec<_>>()).await;
+    assert_eq!(res.expect("running query"), 10_000_000);
+    for update in updates {
+        assert!((0.0..=100.0).contains(&update.percentage), "{update:?}");
+        assert!(update.rows_processed <= update.total_rows, "{update:?}");
+    }
+
+    let print: bool = client
+        .conn(|conn| {
+            conn.query_row(
+                "SELECT current_setting('enable_progress_bar_print')",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .await
+        .unwrap();
+    assert!(print);
+
+    // the error of the function is returned even though the settings cannot
+    // be restored in the aborted transaction
+    let (res, _) = client.conn_with_progress(|conn| {
+        conn.execute_batch("BEGIN; SELECT * FROM missing;")?;
+        Ok(())
+    });
+    let err = res.await.unwrap_err();
+    assert!(err.is_catalog_error(), "{err}");
+    client
+        .conn(|conn| conn.execute_batch("ROLLBACK"))
+        .await
+        .expect("rolling back");
+}
+
+async fn test_migrations() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let dir = tmp_dir.path().join("migrations");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(
+        dir.join("0001_users.sql"),
+        "CREATE TABLE users (id INTEGER);",
+    )
+    .unwrap();
+    std::fs::write(dir.join("0001_users.down.sql"), "DROP TABLE users;").unwrap();
+    std::fs::write(
+        dir.join("0002_names.sql"),
+        "ALTER TABLE users ADD COLUMN name VARCHAR;",
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "ignored").unwrap();
+    let migrations = Migrations::from_dir(&dir).expect("reading migrations");
+    let path = tmp_dir.path().join("duck.db");
+
+    let client = ClientBuilder::new()
+        .path(&path)
+        .migrations(migrations.clone().dry_run(true))
+        .open()
+        .await
+        .expect("dry run");
+    let exists: bool = client
+        .conn(|conn| {
+            conn.query_row(
+                "SELECT count(*) > 0 FROM duckdb_tables() WHERE table_name = 'users'",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .await
+        .unwrap();
+    assert!(!exists);
+    assert_eq!(client.migrate(&migrations).await.unwrap(), [1, 2]);
+    assert!(client.migrate(&migrations).await.unwrap().is_empty());
+
+    let err = client
+        .migrate_down(&migrations, 0)
+        .await
+        .expect_err("migration 2 has no down SQL");
+    assert_eq!(err.kind(), ErrorKind::Migration);
+    let migrations = migrations.migration(
+        Migration::new(2, "names", "ALTER TABLE users ADD COLUMN name VARCHAR;")
+            .down("ALTER TABLE users DROP COLUMN name;"),
+    );
+    assert_eq!(client.migrate_down(&migrations, 1).await.unwrap(), [2]);
+    client.close().await.unwrap();
+
+    let changed = migrations.clone().migration(Migration::new(
+        1,
+        "users",
+        "CREATE TABLE users (id BIGINT);",
+    ));
+    let err = ClientBuilder::new()
+        .path(&path)
+        .migrations(changed)
+        .open()
+        .await
+        .map(|_| ())
+        .expect_err("migration 1 has drifted");
+    assert!(
+        matches!(err, Error::MigrationDrift { version: 1, .. }),
+        "{err}"
+    );
+
+    let client = ClientBuilder::new()
+        .path(&path)
+        .migrations(migrations)
+        .open()
+        .await
+        .expect("reapplying migration 2");
+    let columns: i64 = client
+        .conn(|conn| {
+            conn.query_row(
+                "SELECT count(*) FROM duckdb_columns() WHERE table_name = 'users'",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(columns, 2);
+}
+
+async fn test_schema_introspection() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR NOT NULL DEFAULT 'anon');
+                 CREATE TABLE posts (id INTEGER, user_id INTEGER REFERENCES users (id));
+                 CREATE INDEX posts_user ON posts (user_id);
+                 CREATE VIEW names AS SELECT name FROM users;
+                 CREATE MACRO twice(x) AS x * 2;",
+            )
+        })
+        .await
+        .unwrap();
+
+    let databases = client.databases().await.unwrap();
+    assert!(
+        databases
+            .iter()
+            .any(|db| db.name == "memory" && !db.internal)
+    );
+    let schemas = client.schemas().await.unwrap();
+    assert!(
+        schemas
+            .iter()
+            .any(|s| s.database == "memory" && s.name == "main")
+    );
+
+    let tables = client.tables().await.unwrap();
+    let names: Vec<_> = tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["posts", "users"]);
+    assert!(tables[1].has_primary_key);
+    assert_eq!(tables[1].column_count, 2);
+
+    let views = client.views().await.unwrap();
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].name, "names");
+
+    let columns = client.columns("users").await.unwrap();
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns[0].name, "id");
+    assert_eq!(columns[0].data_type, "INTEGER");
+    assert!(!columns[1].nullable);
+    assert_eq!(columns[1].default.as_deref(), Some("'anon'"));
+
+    let indexes = client.indexes().await.unwrap();
+    assert!(
+        indexes
+            .iter()
+            .any(|i| i.name == "posts_user" && i.table == "posts")
+    );
+
+    let constraints = client.constraints("posts").await.unwrap();
+    let fk = constraints
+        .iter()
+        .find(|c| c.kind == "FOREIGN KEY")
+        .expect("foreign key");
+    assert_eq!(fk.columns, ["user_id"]);
+    assert_eq!(fk.referenced_table.as_deref(), Some("users"));
+    assert_eq!(fk.referenced_columns, ["id"]);
+
+    let functions = client.functions().await.unwrap();
+    let twice = functions.iter().find(|f| f.name == "twice").expect("macro");
+    assert_eq!(twice.parameters, ["x"]);
+    assert!(!twice.internal);
+}
+
+async fn test_pool_reload() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = tmp_dir.path().join("duck.db");
+    // like a batch job: write a new file, then move it over the served one
+    let publish = |version: i32| {
+        let next = tmp_dir.path().join("next.db");
+        let client = ClientBuilder::new().path(&next).open_blocking().unwrap();
+        client
+            .conn_blocking(move |conn| {
+                conn.execute_batch(&format!("CREATE TABLE version AS SELECT {version} AS v"))
+            })
+            .unwrap();
+        client.close_blocking().unwrap();
+        std::fs::rename(&next, &path).unwrap();
+    };
+    let versions = |pool: &async_duckdb::Pool| {
+        pool.conn_for_each_blocking(|conn| {
+            conn.query_row("SELECT v FROM version", [], |row| row.get::<_, i32>(0))
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>, Error>>()
+        .unwrap()
+    };
+
+    publish(1);
+    let pool = PoolBuilder::new()
+        .path(&path)
+        .num_conns(2)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    assert_eq!(versions(&pool), [1, 1]);
+    publish(2);
+    assert_eq!(versions(&pool), [1, 1]);
+    pool.reload().await.expect("reloading pool");
+    assert_eq!(versions(&pool), [2, 2]);
+
+    let watched = PoolBuilder::new()
+        .path(&path)
+        .num_conns(2)
+        .watch(Duration::from_millis(10))
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    publish(3);
+    let mut tries = 0;
+    while versions(&watched) != [3, 3] {
+        tries += 1;
+        assert!(tries < 500, "pool was not reloaded");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    pool.close().await.unwrap();
+    assert!(matches!(pool.reload().await, Err(Error::Closed)));
+    watched.close().await.unwrap();
+}
+
+async fn test_maintenance() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let client = ClientBuilder::new()
+        .path(tmp_dir.path().join("duck.db"))
+        .maintenance(
+            MaintenancePolicy::new()
+                .wal_size(0)
+                .poll_interval(Duration::from_millis(10))
+                .checkpoint_threshold("1GB"),
+        )
+        .open()
+        .await
+        .expect("client unable to be opened");
+    assert_eq!(client.last_checkpoint(), None);
+
+    client
+        .conn(|conn| conn.execute_batch("CREATE TABLE t AS SELECT * FROM range(1000)"))
+        .await
+        .unwrap();
+    let mut tries = 0;
+    let info = loop {
+        if let Some(info) = client.last_checkpoint() {
+            break info;
+        }
+        tries += 1;
+        assert!(tries < 500, "no checkpoint was run");
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    assert!(info.wal_size > 0);
+    assert!(!info.forced);
+    client.close().await.unwrap();
+}
+
+async fn test_database_copies() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let count = |client: async_duckdb::Client| async move {
+        client
+            .conn(|conn| conn.query_row("SELECT sum(i) FROM t", [], |row| row.get::<_, i64>(0)))
+            .await
+            .unwrap()
+    };
+
+    let client = ClientBuilder::new().open().await.unwrap();
+    client
+        .conn(|conn| conn.execute_batch("CREATE TABLE t AS SELECT range AS i FROM range(100)"))
+        .await
+        .unwrap();
+    let path = tmp_dir.path().join("copy.db");
+    let persisted = client.persist_to(&path).await.expect("persisting database");
+    assert_eq!(persisted.bytes, std::fs::metadata(&path).unwrap().len());
+    let dir = tmp_dir.path().join("export");
+    let exported = client
+        .export_database(&dir, CsvOptions::new())
+        .await
+        .expect("exporting database");
+    assert!(exported.bytes > 0);
+
+    let loaded = ClientBuilder::new().open().await.unwrap();
+    loaded.load_from(&path).await.expect("loading database");
+    assert_eq!(count(loaded).await, 4950);
+    let imported = ClientBuilder::new().open().await.unwrap();
+    let copy = imported
+        .import_database(&dir)
+        .await
+        .expect("importing database");
+    assert_eq!(copy.bytes, exported.bytes);
+    assert_eq!(count(imported).await, 4950);
+}
+
+async fn test_pool_resize() {
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    pool.resize(4).await.expect("growing pool");
+    assert_eq!(pool.num_conns(), 4);
+    assert_eq!(pool.conn_for_each(|_| Ok(())).await.len(), 4);
+    pool.resize(1).await.expect("shrinking pool");
+    assert_eq!(pool.num_conns(), 1);
+    pool.conn(|_| Ok(())).await.unwrap();
+    let err = pool.resize(0).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io, "{err}");
+    assert_eq!(pool.num_conns(), 1);
+    let err = PoolBuilder::new().num_conns(0).open().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io, "{err}");
+
+    let wait_for = |pool: &async_duckdb::Pool, num_conns: usize| {
+        let mut tries = 0;
+        while pool.num_conns() != num_conns {
+            tries += 1;
+            assert!(tries < 500, "pool did not reach {num_conns} connections");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+    let elastic = PoolBuilder::new()
+        .elastic(1, 3, Duration::from_millis(200))
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    assert_eq!(elastic.num_conns(), 1);
+    let busy = elastic.clone();
+    let slow = std::thread::spawn(move || {
+        busy.conn_blocking(|_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        })
+    });
+    std::thread::sleep(Duration::from_millis(50));
+    // every connection is busy, so this call opens another one
+    let busy = elastic.clone();
+    let queued = std::thread::spawn(move || busy.conn_blocking(|_| Ok(())));
+    wait_for(&elastic, 2);
+    slow.join().unwrap().unwrap();
+    queued.join().unwrap().unwrap();
+    wait_for(&elastic, 1);
+    elastic.close().await.unwrap();
+}
+
+async fn test_priority() {
+    let client = ClientBuilder::new().open().await.unwrap();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let push = |client: async_duckdb::Client, name: &'static str| {
+        let order = order.clone();
+        std::thread::spawn(move || {
+            client.conn_blocking(move |_| {
+                order.lock().unwrap().push(name);
+                Ok(())
+            })
+        })
+    };
+
+    let busy = client.clone();
+    let slow = std::thread::spawn(move || {
+        busy.conn_blocking(|_| {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(())
+        })
+    });
+    std::thread::sleep(Duration::from_millis(50));
+    let normal = push(client.clone(), "normal");
+    std::thread::sleep(Duration::from_millis(20));
+    let high = client.high_priority();
+    assert_eq!(high.priority(), Priority::High);
+    let high = push(high, "high");
+    for handle in [slow, normal, high] {
+        handle.join().unwrap().unwrap();
+    }
+    assert_eq!(*order.lock().unwrap(), ["high", "normal"]);
+
+    let pool = PoolBuilder::new().num_conns(2).open().await.unwrap();
+    let value: i32 = pool
+        .high_priority()
+        .conn(|conn| conn.query_row("SELECT 42", [], |row| row.get(0)))
+        .await
+        .unwrap();
+    assert_eq!(value, 42);
+}
+
+async fn test_group_commit() {
+    let client = ClientBuilder::new()
+        .group_commit(Duration::from_millis(50))
+        .open()
+        .await
+        .unwrap();
+    client
+        .conn(|conn| conn.execute_batch("CREATE TABLE t (i INTEGER PRIMARY KEY)"))
+        .await
+        .unwrap();
+
+    // the duplicate key fails, the other writes share one commit
+    let writes = [1, 2, 3, 2, 4]
+        .map(|i| client.write(move |conn| conn.execute("INSERT INTO t VALUES (?)", [i])));
+    let results = join_all(writes).await;
+    assert!(results[..3].iter().all(Result::is_ok));
+    assert!(results[3].as_ref().unwrap_err().is_constraint_violation());
+    assert!(results[4].is_ok());
+    let count: i64 = client
+        .conn(|conn| conn.query_row("SELECT count(*) FROM t", [], |row| row.get(0)))
+        .await
+        .unwrap();
+    assert_eq!(count, 4);
+
+    let rows = ClientBuilder::new()
+        .open_blocking()
+        .unwrap()
+        .write_blocking(|conn| {
+            conn.execute_batch("CREATE TABLE u (i INTEGER)")?;
+            conn.execute("INSERT INTO u VALUES (1)", [])
+        })
+        .unwrap();
+    assert_eq!(rows, 1);
+}
+
+async fn test_batch() {
+    let client = ClientBuilder::new().open().await.unwrap();
+    let rows = client
+        .batch()
+        .execute("CREATE TABLE t (i INTEGER PRIMARY KEY)")
+        .execute("INSERT INTO t VALUES (1), (2)")
+        .conn(|conn| conn.execute("INSERT INTO t VALUES (3)", []))
+        .run()
+        .await
+        .expect("running batch");
+    assert_eq!(rows[1..], [2, 1]);
+
+    let err = client
+        .batch()
+        .transaction(true)
+        .execute("INSERT INTO t VALUES (4)")
+        .execute("INSERT INTO t VALUES (1)")
+        .run()
+        .await
+        .expect_err("duplicate key");
+    assert!(err.is_constraint_violation());
+    let values: Vec<i32> = client
+        .batch()
+        .conn(|conn| conn.query_row("SELECT count(*) FROM t", [], |row| row.get(0)))
+        .conn(|conn| conn.query_row("SELECT max(i) FROM t", [], |row| row.get(0)))
+        .run_blocking()
+        .unwrap();
+    assert_eq!(values, [3, 3]);
+}
+
+async fn test_thread_builder() {
+    let started = Arc::new(AtomicU32::new(0));
+    let stopped = Arc::new(AtomicU32::new(0));
+    let (started_, stopped_) = (started.clone(), stopped.clone());
+    let threads = ThreadBuilder::new()
+        .name("duck")
+        .stack_size(4 * 1024 * 1024)
+        .on_thread_start(move || _ = started_.fetch_add(1, SeqCst))
+        .on_thread_stop(move || _ = stopped_.fetch_add(1, SeqCst));
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .thread_builder(threads)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    assert_eq!(started.load(SeqCst), 2);
+
+    let names: HashSet<String> = pool
+        .conn_for_each(|_| Ok(std::thread::current().name().map(str::to_owned)))
+        .await
+        .into_iter()
+        .map(|name| name.unwrap().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        HashSet::from(["duck-0".to_owned(), "duck-1".to_owned()])
+    );
+
+    pool.close().await.unwrap();
+    // closing joins the worker threads
+    assert_eq!(stopped.load(SeqCst), 2);
+}
+
+async_test!(test_pool_close);
+
+async fn test_pool_close() {
+    let pool = PoolBuilder::new()
+        .num_conns(3)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+
+    // the running queries are interrupted at the same deadline
+    let long = pool.conn_for_each(|conn| {
+        conn.query_row("SELECT sum(i) FROM range(1000000000000) t(i)", [], |row| {
+            row.get::<_, i128>(0)
+        })
+    });
+    let close = pool.close_with(ClosePolicy::new().grace_period(Duration::from_millis(100)));
+    let (long, closed) = join(long, close).await;
+    assert!(
+        long.iter()
+            .all(|res| res.as_ref().unwrap_err().is_interrupted())
+    );
+    closed.unwrap();
+
+    let err = Error::Multiple(vec![Error::Closed, Error::Closed]);
+    assert_eq!(err.kind(), ErrorKind::Multiple);
+    assert_eq!(
+        err.to_string(),
+        "2 errors: connection to sqlite database closed; connection to sqlite database closed"
+    );
+
+    // the connections of a pool that fails to open are closed again
+    let stopped = Arc::new(AtomicU32::new(0));
+    let stopped_ = stopped.clone();
+    let threads = ThreadBuilder::new()
+        .name("failing")
+        .on_thread_start(|| {
+            assert_ne!(std::thread::current().name(), Some("failing-1"));
+        })
+        .on_thread_stop(move || _ = stopped_.fetch_add(1, SeqCst));
+    let res = PoolBuilder::new()
+        .num_conns(3)
+        .thread_builder(threads)
+        .open()
+        .await;
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::Io);
+    assert_eq!(stopped.load(SeqCst), 2);
+}
+
+async_test!(test_health);
+
+async fn test_health() {
+    let client = ClientBuilder::new()
+        .health_check(HealthCheck::new().timeout(Duration::from_millis(100)))
+        .open()
+        .await
+        .expect("client unable to be opened");
+    let health = client.health().await;
+    assert_eq!(health.client_id, client.id());
+    assert_eq!(health.status, HealthStatus::Healthy);
+    assert_eq!(health.queue_depth, 0);
+    assert!(health.latency.is_some());
+    assert_eq!(health.last_error, None);
+
+    // a probe stuck behind a slow call times out, and is skipped once the
+    // slow call is done
+    let probing = ClientBuilder::new()
+        .health_check(
+            HealthCheck::new()
+                .probe("INSERT INTO probes VALUES (1)")
+                .timeout(Duration::from_millis(100)),
+        )
+        .open()
+        .await
+        .expect("client unable to be opened");
+    probing
+        .conn(|conn| conn.execute_batch("CREATE TABLE probes (i INTEGER)"))
+        .await
+        .unwrap();
+    let (started_tx, started_rx) = futures_channel::oneshot::channel();
+    let slow = probing.conn(|_| {
+        // every earlier call was counted out before this one runs
+        _ = started_tx.send(());
+        std::thread::sleep(Duration::from_millis(300));
+        Ok(())
+    });
+    let probe = async {
+        started_rx.await.unwrap();
+        probing.health().await
+    };
+    let (slow, health) = join(slow, probe).await;
+    slow.unwrap();
+    assert_eq!(health.status, HealthStatus::Unhealthy);
+    assert_eq!(health.queue_depth, 1);
+    assert!(health.last_error.unwrap().contains("timed out"));
+    let count: i64 = probing
+        .conn(|conn| conn.query_row("SELECT count(*) FROM probes", [], |row| row.get(0)))
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+    let health = client.health_blocking();
+    assert_eq!(health.status, HealthStatus::Healthy);
+    client.close().await.unwrap();
+    assert_eq!(client.health().await.status, HealthStatus::Closed);
+
+    // the probe only succeeds on the connection that has the table, and
+    // calls are routed to it
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .health_check(HealthCheck::new().probe("SELECT * FROM ready"))
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    pool.conn(|conn| conn.execute_batch("CREATE TABLE ready (i INTEGER)"))
+        .await
+        .unwrap();
+    let health = pool.health().await;
+    assert_eq!(health.workers.len(), 2);
+    assert!(health.is_ready());
+    assert!(!health.is_healthy());
+    let statuses = |health: async_duckdb::PoolHealth| {
+        health
+            .workers
+            .into_iter()
+            .map(|worker| worker.status)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(statuses(pool.health_blocking()), statuses(health));
+    for _ in 0..4 {
+        pool.conn(|conn| conn.execute_batch("INSERT INTO ready VALUES (1)"))
+            .await
+            .unwrap();
+    }
+}
+
+async_test!(test_cursor);
+
+async fn test_cursor() {
+    use async_duckdb::duckdb::arrow::{array::Int64Array, record_batch::RecordBatch};
+
+    let values = |batch: &RecordBatch| {
+        batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .values()
+            .to_vec()
+    };
+    let client = ClientBuilder::new()
+        .cursor_ttl(Duration::from_millis(200))
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| conn.execute_batch("CREATE TABLE t AS SELECT range AS i FROM range(10)"))
+        .await
+        .unwrap();
+
+    let cursor = client
+        .open_cursor("SELECT i FROM t WHERE i >= ? ORDER BY i", [2])
+        .await
+        .unwrap();
+    assert_eq!(cursor.column_names(), ["i"]);
+    // rows inserted after opening are not seen
+    client
+        .conn(|conn| conn.execute_batch("INSERT INTO t VALUES (10)"))
+        .await
+        .unwrap();
+    assert_eq!(values(&cursor.fetch(3).await.unwrap()), [2, 3, 4]);
+    assert_eq!(values(&cursor.fetch_blocking(10).unwrap()), [5, 6, 7, 8, 9]);
+    assert_eq!(cursor.fetch(1).await.unwrap().num_rows(), 0);
+    cursor.close().await.unwrap();
+
+    // pages span the chunks of the result
+    let cursor = client
+        .open_cursor("SELECT range AS i FROM range(5000)", ())
+        .await
+        .unwrap();
+    assert_eq!(values(&cursor.fetch(3).await.unwrap()), [0, 1, 2]);
+    let page = cursor.fetch(4990).await.unwrap();
+    assert_eq!(values(&page)[4989], 4992);
+    assert_eq!(
+        values(&cursor.fetch(10).await.unwrap()),
+        [4993, 4994, 4995, 4996, 4997, 4998, 4999]
+    );
+    cursor.close().await.unwrap();
+
+    // the query runs on the connection of the client, as written
+    client
+        .conn(|conn| conn.execute_batch("CREATE TEMP TABLE tmp AS SELECT range AS i FROM range(4)"))
+        .await
+        .unwrap();
+    let cursor = client
+        .open_cursor("SELECT i FROM tmp ORDER BY i; -- the temp table", ())
+        .await
+        .unwrap();
+    assert_eq!(values(&cursor.fetch(10).await.unwrap()), [0, 1, 2, 3]);
+    cursor.close().await.unwrap();
+    let cursor = client
+        .open_cursor("PRAGMA table_info('tmp')", ())
+        .await
+        .unwrap();
+    assert_eq!(cursor.column_names()[1], "name");
+    assert_eq!(cursor.fetch(10).await.unwrap().num_rows(), 1);
+    cursor.close().await.unwrap();
+
+    // idle cursors expire
+    let cursor = client.open_cursor("SELECT i FROM t", ()).await.unwrap();
+    std::thread::sleep(Duration::from_millis(400));
+    let err = cursor.fetch(1).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::CursorExpired);
+    cursor.close().await.unwrap();
+
+    // a cursor stays on the pool worker it was opened on
+    let pool = PoolBuilder::new()
+        .num_conns(2)
+        .open()
+        .await
+        .expect("pool unable to be opened");
+    let worker = pool.client(1).unwrap();
+    worker
+        .conn(|conn| conn.execute_batch("CREATE TABLE u AS SELECT range AS i FROM range(3)"))
+        .await
+        .unwrap();
+    let cursor = worker.open_cursor("SELECT i FROM u", ()).await.unwrap();
+    for _ in 0..3 {
+        pool.conn(|_| Ok(())).await.unwrap();
+        assert_eq!(cursor.fetch(1).await.unwrap().num_rows(), 1);
+    }
+    assert!(pool.client(2).is_none());
+}
+
+async_test!(test_prepare);
+
+async fn test_prepare() {
+    use async_duckdb::duckdb::arrow::record_batch::RecordBatch;
+
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    client
+        .conn(|conn| conn.execute_batch("CREATE TABLE kv (k INTEGER, v VARCHAR)"))
+        .await
+        .unwrap();
+
+    let insert = client
+        .prepare("INSERT INTO kv VALUES (?, ?)")
+        .await
+        .unwrap();
+    assert_eq!(insert.parameter_count(), 2);
+    assert!(insert.columns().is_empty());
+    for (k, v) in [(1, "a"), (2, "b"), (3, "c")] {
+        assert_eq!(insert.execute((k, v)).await.unwrap(), 1);
+    }
+    assert_eq!(insert.execute_blocking((4, "d")).unwrap(), 1);
+
+    let select = client
+        .prepare("SELECT k, v FROM kv WHERE k >= ? ORDER BY k")
+        .await
+        .unwrap();
+    assert_eq!(select.parameter_count(), 1);
+    let columns: Vec<_> = select
+        .columns()
+        .iter()
+        .map(|column| (column.name.as_str(), column.data_type.as_str()))
+        .collect();
+    assert_eq!(columns, [("k", "INTEGER"), ("v", "VARCHAR")]);
+    let rows = select
+        .query_map([3], |row| row.get::<_, String>(1))
+        .await
+        .unwrap();
+    assert_eq!(rows, ["c", "d"]);
+    let batches = select.query_arrow([2]).await.unwrap();
+    assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 3);
+    let rows = select
+        .query_map_blocking([4], |row| row.get::<_, i32>(0))
+        .unwrap();
+    assert_eq!(rows, [4]);
+    select.close().await.unwrap();
+
+    let err = client.prepare("SELECT * FROM missing").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Catalog);
+    let ctx = err.context().unwrap();
+    assert_eq!(ctx.sql.as_deref(), Some("SELECT * FROM missing"));
+    assert_eq!(ctx.client_id, Some(client.id()));
+    assert!(ctx.elapsed.is_some());
+    drop(insert);
+    client.close().await.unwrap();
+}
+
+async_test!(test_prepare_connection);
+
+async fn test_prepare_connection() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+
+    // statements run on the connection of the client and outlive its
+    // statement cache
+    client
+        .conn(|conn| {
+            conn.set_prepared_statement_cache_capacity(1);
+            conn.execute_batch(
+                "CREATE TEMP TABLE tmp AS SELECT 42 AS i; CREATE TYPE kv_key AS INTEGER",
+            )
+        })
+        .await
+        .unwrap();
+    let temp = client
+        .prepare("SELECT i FROM tmp -- the temp table")
+        .await
+        .unwrap();
+    assert_eq!(temp.columns().len(), 1);
+    let show = client.prepare("SHOW TABLES;").await.unwrap();
+    assert_eq!(show.columns()[0].name, "name");
+    let pragma = client.prepare("PRAGMA version").await.unwrap();
+    assert_eq!(pragma.columns()[0].name, "library_version");
+    let set = client.prepare("SET threads = 2").await.unwrap();
+    assert!(set.columns().is_empty());
+    for _ in 0..2 {
+        let rows = temp
+            .query_map((), |row| row.get::<_, i32>(0))
+            .await
+            .unwrap();
+        assert_eq!(rows, [42]);
+        let rows = show
+            .query_map((), |row| row.get::<_, String>(0))
+            .await
+            .unwrap();
+        assert!(rows.iter().any(|name| name == "tmp"));
+    }
+
+    // columns are named like DESCRIBE names them
+    let sql = "SELECT 1::HUGEINT, 1.5::DECIMAL(18,3) AS \"the amount\", \
+        'a'::ENUM('a', 'it''s') AS e, [1]::INTEGER[], [1]::INTEGER[1], \
+        MAP {1: 'a'}, {'a': 1, 'B c': [now()]}, union_value(n := 1), \
+        now(), NULL, 1::kv_key, uuid(), 1::BIGNUM";
+    let described: Vec<_> = client
+        .conn(move |conn| {
+            conn.prepare(&format!("DESCRIBE {sql}"))?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()
+        })
+        .await
+        .unwrap();
+    let stmt = client.prepare(sql).await.unwrap();
+    let columns: Vec<_> = stmt
+        .columns()
+        .iter()
+        .map(|column| (column.name.clone(), column.data_type.clone()))
+        .collect();
+    assert_eq!(columns, described);
+}
+
+async_test!(test_close_policy);
+
+async fn test_close_policy() {
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+
+    // queued calls fail instead of running, the running one finishes
+    let (started_tx, started_rx) = std::sync::mpsc::channel();
+    let slow = {
+        let client = client.clone();
+        std::thread::spawn(move || {
+            client.conn_blocking(move |_| {
+                _ = started_tx.send(());
+                std::thread::sleep(Duration::from_millis(200));
+                Ok(1)
+            })
+        })
+    };
+    started_rx.recv().unwrap();
+    let queued = join_all((0..3).map(|i| client.conn(move |_| Ok(i))));
+    let close = client.close_with(ClosePolicy::new().reject_queued(true));
+    let (queued, closed) = join(queued, close).await;
+    assert_eq!(slow.join().unwrap().unwrap(), 1);
+    assert!(
+        queued
+            .iter()
+            .all(|res| res.as_ref().unwrap_err().kind() == ErrorKind::Closed)
+    );
+    closed.unwrap();
+    let err = client.conn(|_| Ok(())).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Closed);
+
+    // the running query is interrupted after the grace period
+    let client = ClientBuilder::new()
+        .open()
+        .await
+        .expect("client unable to be opened");
+    let long = client.conn(|conn| {
+        conn.query_row("SELECT sum(i) FROM range(1000000000000) t(i)", [], |row| {
+            row.get::<_, i128>(0)
+        })
+    });
+    let close = client.close_with(ClosePolicy::new().grace_period(Duration::from_millis(100)));
+    let (long, closed) = join(long, close).await;
+    assert_eq!(long.unwrap_err().kind(), ErrorKind::Interrupted);
+    closed.unwrap();
+
+    // the connection is closed once the last handle is dropped
+    let stopped = Arc::new(AtomicU32::new(0));
+    let stopped_ = stopped.clone();
+    let errors = Arc::new(AtomicU32::new(0));
+    let errors_ = errors.clone();
+    let client = ClientBuilder::new()
+        .thread_builder(
+            ThreadBuilder::new().on_thread_stop(move || _ = stopped_.fetch_add(1, SeqCst)),
+        )
+        .close_on_drop(move |_| _ = errors_.fetch_add(1, SeqCst))
+        .open()
+        .await
+        .expect("client unable to be opened");
+    let other = client.clone();
+    drop(client);
+    other.conn(|_| Ok(())).await.unwrap();
+    drop(other);
+    let mut tries = 0;
+    while stopped.load(SeqCst) != 1 {
+        tries += 1;
+        assert!(tries < 500, "worker thread did not stop");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(errors.load(SeqCst), 0);
+}
+
+#[cfg(feature = "vscalar")]
+mod vscalar {
+    use std::{pin::pin, sync::Arc, time::Duration};
+
+    use async_duckdb::{
+        AsyncScalar, AsyncScalarOptions, ClientBuilder, Error, ErrorKind, PoolBuilder,
+    };
+    use duckdb::{
+        arrow::{
+            array::{ArrayRef, Int64Array},
+            record_batch::RecordBatch,
+        },
+        core::{DataChunkHandle, LogicalTypeId},
+        vscalar::{ScalarFunctionSignature, VScalar},
+        vtab::arrow::WritableVector,
+    };
+    use futures_util::future::{Either, select};
+
+    /// Multiplies a BIGINT by the state.
+    struct Multiply;
+
+    impl VScalar for Multiply {
+        type State = i64;
+
+        unsafe fn invoke(
+            state: &Self::State,
+            input: &mut DataChunkHandle,
+            output: &mut dyn WritableVector,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            let len = input.len();
+            let values = input.flat_vector(0);
+            let values = unsafe { values.as_slice_with_len::<i64>(len) };
+            let mut out = output.flat_vector();
+            let out = unsafe { out.as_mut_slice_with_len::<i64>(len) };
+            for (o, v) in out.iter_mut().zip(values) {
+                *o = v * state;
+            }
+            Ok(())
+        }
+
+        fn signatures() -> Vec<ScalarFunctionSignature> {
+            vec![ScalarFunctionSignature::exact(
+                vec![LogicalTypeId::Bigint.into()],
+                LogicalTypeId::Bigint.into(),
+            )]
+        }
+    }
+
+    /// Adds one to a BIGINT, asynchronously.
+    struct AddOne;
+
+    impl AsyncScalar for AddOne {
+        fn signatures() -> Vec<ScalarFunctionSignature> {
+            vec![ScalarFunctionSignature::exact(
+                vec![LogicalTypeId::Bigint.into()],
+                LogicalTypeId::Bigint.into(),
+            )]
+        }
+
+        async fn invoke(
+            &self,
+            input: RecordBatch,
+        ) -> Result<ArrayRef, Box<dyn std::error::Error + Send + Sync>> {
+            let values = input
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .ok_or("expected BIGINT")?;
+            Ok(Arc::new(
+                values
+                    .iter()
+                    .map(|v| v.map(|v| v + 1))
+                    .collect::<Int64Array>(),
+            ))
+        }
+    }
+
+    /// Never completes.
+    struct Hang;
+
+    impl AsyncScalar for Hang {
+        fn signatures() -> Vec<ScalarFunctionSignature> {
+            AddOne::signatures()
+        }
+
+        async fn invoke(
+            &self,
+            _: RecordBatch,
+        ) -> Result<ArrayRef, Box<dyn std::error::Error + Send + Sync>> {
+            futures_util::future::pending().await
+        }
+    }
+
+    async_test!(test_register_scalar);
+    async_test!(test_pool_register_scalar);
+    async_test!(test_register_async_scalar);
+
+    async fn test_register_scalar() {
+        let client = ClientBuilder::new()
+            .open()
+            .await
+            .expect("client unable to be opened");
+        client
+            .register_scalar::<Multiply>("zero")
+            .await
+            .expect("registering scalar");
+        client
+            .register_scalar_with_state::<Multiply>("triple", 3)
+            .await
+            .expect("registering scalar");
+        let val: i64 = client
+            .conn(|conn| conn.query_row("SELECT triple(14)", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(val, 42);
+        let val: i64 = client
+            .conn(|conn| conn.query_row("SELECT zero(14)", [], |row| row.get(0)))
+            .await
+            .unwrap();
+        assert_eq!(val, 0);
+    }
+
+    async fn test_pool_register_scalar() {
+        let pool = PoolBuilder::new()
+            .num_conns(2)
+            .open()
+            .await
+            .expect("pool unable to be opened");
+        pool.register_scalar_with_state::<Multiply>("double", 2)
+            .await
+            .expect("registering scalar");
+        let res = pool
+            .conn_for_each(|conn| {
+                conn.query_row("SELECT double(21)", [], |row| row.get::<_, i64>(0))
+            })
+            .await;
+        for r in res {
+            assert_eq!(r.unwrap(), 42);
+        }
+
+        // registering a name again is rejected, keeping the first function
+        let err = pool
+            .register_scalar_with_state::<Multiply>("double", 3)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::AlreadyRegistered { name } if name == "double"),
+            "{err}"
+        );
+        let val = pool
+            .conn(|conn| conn.query_row("SELECT double(21)", [], |row| row.get::<_, i64>(0)))
+            .await
+            .unwrap();
+        assert_eq!(val, 42);
+
+        // a scalar cannot shadow an aggregate, so registering fails and is
+        // not applied to connections opened later
+        let err = pool
+            .register_scalar_with_state::<Multiply>("sum", 2)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Registration, "{err}");
+        pool.resize(3).await.expect("opening another connection");
+        let res = pool
+            .conn_for_each(|conn| {
+                conn.query_row("SELECT double(21)", [], |row| row.get::<_, i64>(0))
+            })
+            .await;
+        assert_eq!(res.len(), 3);
+        for r in res {
+            assert_eq!(r.unwrap(), 42);
+        }
+        pool.close().await.expect("closing pool");
+    }
+
+    async fn test_register_async_scalar() {
+        let client = ClientBuilder::new()
+            .open()
+            .await
+            .expect("client unable to be opened");
+        let driver = client
+            .register_async_scalar(
+                "add_one",
+                AddOne,
+                AsyncScalarOptions::new().max_concurrency(2),
+            )
+            .await
+            .expect("registering async scalar");
+        let query = client.conn(|conn| {
+            conn.query_row("SELECT sum(add_one(range)) FROM range(5000)", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        });
+        let Either::Right((res, driver)) = select(driver, pin!(query)).await else {
+            unreachable!("driver completed before the query");
+        };
+        assert_eq!(res.expect("calling async scalar"), (1..=5000).sum::<i64>());
+
+        let hang = client
+            .register_async_scalar(
+                "hang",
+                Hang,
+                AsyncScalarOptions::new().timeout(Duration::from_millis(50)),
+            )
+            .await
+            .expect("registering async scalar");
+        let query =
+            client.conn(|conn| conn.query_row("SELECT hang(1)", [], |row| row.get::<_, i64>(0)));
+        let drivers = futures_util::future::join(driver, hang);
+        let Either::Right((res, _)) = select(pin!(drivers), pin!(query)).await else {
+            unreachable!("drivers completed before the query");
+        };
+        let err = res.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+    }
+}
+
+#[cfg(feature = "vtab")]
+mod vtab {
+    use std::{pin::pin, sync::Arc};
+
+    use async_duckdb::ClientBuilder;
+    use duckdb::arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use futures_util::future::{Either, select};
+
+    async_test!(test_stream_table);
+
+    async fn test_stream_table() {
+        let client = ClientBuilder::new()
+            .open()
+            .await
+            .expect("client unable to be opened");
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let schema_ = schema.clone();
+        let driver = client
+            .register_stream_table("live_events", schema, move || {
+                let ids = Int64Array::from_iter_values(0..5000);
+                let batch = RecordBatch::try_new(schema_.clone(), vec![Arc::new(ids)]).unwrap();
+                futures_util::stream::iter([batch.clone(), batch])
+            })
+            .await
+            .expect("registering stream table");
+
+        let query = client.conn(|conn| {
+            conn.query_row("SELECT count(*), sum(id) FROM live_events()", [], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })
+        });
+        let Either::Right((res, _)) = select(driver, pin!(query)).await else {
+            unreachable!("driver completed before the query");
+        };
+        let (count, sum) = res.expect("querying stream table");
+        assert_eq!(count, 10_000);
+        assert_eq!(sum, 2 * (0..5000).sum::<i64>());
+    }
+}
+
+#[cfg(feature = "vtab-arrow")]
+mod vtab_arrow {
+    use std::sync::Arc;
+
+    use async_duckdb::{ErrorKind, PoolBuilder};
+    use duckdb::arrow::{
+        array::{Int64Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+
+    async_test!(test_register_arrow);
+
+    async fn test_register_arrow() {
+        let pool = PoolBuilder::new()
+            .num_conns(2)
+            .open()
+            .await
+            .expect("pool unable to be opened");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let ids = Int64Array::from_iter_values(0..3000);
+        let names = StringArray::from_iter_values((0..3000).map(|i| format!("user {i}")));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(ids), Arc::new(names)])
+            .expect("building batch");
+        pool.register_arrow("users", schema, vec![batch.clone(), batch])
+            .await
+            .expect("registering arrow view");
+
+        pool.conn_for_each(|conn| {
+            conn.query_row("SELECT count(*) FROM users", [], |row| row.get::<_, i64>(0))
+        })
+        .await
+        .into_iter()
+        .for_each(|res| assert_eq!(res.expect("querying arrow view"), 6000));
+
+        let name: String = pool
+            .conn(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE orders (user_id BIGINT); INSERT INTO orders VALUES (42);",
+                )?;
+                conn.query_row(
+                    "SELECT DISTINCT name FROM orders JOIN users ON users.id = orders.user_id",
+                    [],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .expect("joining arrow view");
+        assert_eq!(name, "user 42");
+
+        pool.unregister_arrow("users")
+            .await
+            .expect("unregistering arrow view");
+        let err = pool
+            .conn(|conn| conn.execute_batch("SELECT * FROM users"))
+            .await
+            .expect_err("arrow view should be dropped");
+        assert_eq!(err.kind(), ErrorKind::Catalog);
+    }
+}
+
+#[cfg(feature = "polars")]
+mod polars {
+    use async_duckdb::{ClientBuilder, WriteMode};
+    use polars_core::{df, prelude::DataType};
+
+    async_test!(test_polars_round_trip);
+    async_test!(test_polars_empty_result);
+
+    async fn test_polars_round_trip() {
+        let client = ClientBuilder::new()
+            .open()
+            .await
+            .expect("client unable to be opened");
+        let df = df!(
+            "id" => [1i64, 2, 3],
+            "name" => ["a", "b", "c"],
+        )
+        .expect("building data frame");
+
+        let rows = client
+            .write_polars("letters", &df, WriteMode::Create)
+            .await
+            .expect("creating table");
+        assert_eq!(rows, 3);
+        client
+            .write_polars("letters", &df, WriteMode::Append)
+            .await
+            .expect("appending to table");
+        client
+            .write_polars("letters", &df, WriteMode::Create)
+            .await
+            .expect_err("table already exists");
+
+        let out = client
+            .query_polars("SELECT * FROM letters WHERE id > ? ORDER BY id", [1i64])
+            .await
+            .expect("querying table");
+        assert_eq!(out.shape(), (4, 2));
+
+        client
+            .write_polars("letters", &df, WriteMode::Replace)
+            .await
+            .expect("replacing table");
+        let out = client
+            .query_polars("SELECT * FROM letters", ())
+            .await
+            .expect("querying table");
+        assert_eq!(out.height(), 3);
+    }
+
+    async fn test_polars_empty_result() {
+        let client = ClientBuilder::new()
+            .open()
+            .await
+            .expect("client unable to be opened");
+        let out = client
+            .query_polars("SELECT 1::BIGINT AS id, 'a' AS name WHERE false", ())
+            .await
+            .expect("querying empty result");
+        assert_eq!(out.shape(), (0, 2));
+        let id = out.column("id").expect("id column");
+        assert_eq!(id.dtype(), &DataType::Int64);
+        let name = out.column("name").expect("name column");
+        assert_eq!(name.dtype(), &DataType::String);
+
+        let rows = client
+            .write_polars("no_rows", &out, WriteMode::Create)
+            .await
+            .expect("creating table from empty frame");
+        assert_eq!(rows, 0);
+        let count: i64 = client
+            .conn(|conn| conn.query_row("SELECT count(*) FROM no_rows", [], |row| row.get(0)))
+            .await
+            .expect("counting rows");
+        assert_eq!(count, 0);
+    }
+}